@@ -1,14 +1,15 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
 
-use std::cell::RefCell;
-use std::ptr::null_mut;
-use std::os::raw::{c_void, c_int, c_float, c_double, c_char};
+use std::os::raw::{c_void, c_int, c_uint, c_double, c_char};
 
-type em_callback_func = extern "C" fn();
-type em_str_callback_func = extern "C" fn(*const c_char);
-type em_arg_callback_func = extern "C" fn(*const c_void);
-type em_run_preload_plugins_data_onload_func = extern "C" fn(*const c_void, *mut c_char);
+pub type em_callback_func = extern "C" fn();
+pub type em_arg_callback_func = extern "C" fn(*mut c_void);
+pub type em_str_callback_func = extern "C" fn(*const c_char);
+pub type em_async_wget_onload_func = extern "C" fn(*mut c_void, *mut c_void, c_int);
+pub type em_async_wget2_onload_func = extern "C" fn(c_uint, *mut c_void, *const c_char);
+pub type em_async_wget2_onstatus_func = extern "C" fn(c_uint, *mut c_void, c_int);
+pub type em_run_preload_plugins_data_onload_func = extern "C" fn(*mut c_void, *const c_char);
 
 extern "C" {
     pub fn emscripten_set_main_loop(func: em_callback_func,
@@ -17,17 +18,25 @@ extern "C" {
 
     pub fn emscripten_cancel_main_loop();
     pub fn emscripten_pause_main_loop();
-    pub fn emscripten_get_now() -> c_float;
+    pub fn emscripten_resume_main_loop();
+    pub fn emscripten_get_now() -> c_double;
+    pub fn emscripten_async_call(func: em_arg_callback_func, arg: *mut c_void, millis: c_int);
+
     pub fn emscripten_run_preload_plugins(file: *const c_char,
                                           onload: em_str_callback_func,
-                                          onerror: em_str_callback_func) -> i32;
-    pub fn emscripten_run_preload_plugins_data(data: *const u8,
-                                               size: usize,
+                                          onerror: em_str_callback_func) -> c_int;
+    pub fn emscripten_run_preload_plugins_data(data: *const c_char,
+                                               size: c_int,
                                                suffix: *const c_char,
-                                               arg: *const c_void,
+                                               arg: *mut c_void,
                                                onload: em_run_preload_plugins_data_onload_func,
                                                onerror: em_arg_callback_func);
 
+    pub fn emscripten_run_script(script: *const c_char);
+    pub fn emscripten_run_script_int(script: *const c_char) -> c_int;
+    /// the result is owned by emscripten and valid until the next call
+    pub fn emscripten_run_script_string(script: *const c_char) -> *const c_char;
+
     pub fn emscripten_asm_const(code: *const c_char);
     pub fn emscripten_asm_const_int(code: *const c_char, ...) -> c_int;
     pub fn emscripten_asm_const_double(code: *const c_char, ...) -> c_double;
@@ -37,29 +46,17 @@ extern "C" {
     pub fn emscripten_debugger();
 
     pub fn emscripten_async_wget(url: *const c_char, file: *const c_char, onload: em_str_callback_func, onerror: em_str_callback_func);
+    pub fn emscripten_async_wget_data(url: *const c_char,
+                                      arg: *mut c_void,
+                                      onload: em_async_wget_onload_func,
+                                      onerror: em_arg_callback_func);
+    /// returns a handle of the request
+    pub fn emscripten_async_wget2(url: *const c_char,
+                                  file: *const c_char,
+                                  requesttype: *const c_char,
+                                  param: *const c_char,
+                                  arg: *mut c_void,
+                                  onload: em_async_wget2_onload_func,
+                                  onerror: em_async_wget2_onstatus_func,
+                                  onprogress: Option<em_async_wget2_onstatus_func>) -> c_int;
 }
-
-thread_local!(static MAIN_LOOP_CALLBACK: RefCell<*mut c_void> = RefCell::new(null_mut()));
-
-pub fn set_main_loop_callback<F>(callback: F)
-    where F: FnMut()
-{
-    MAIN_LOOP_CALLBACK
-        .with(|log| { *log.borrow_mut() = &callback as *const _ as *mut c_void; });
-
-    unsafe {
-        emscripten_set_main_loop(wrapper::<F>, -1, 1);
-    }
-
-    extern "C" fn wrapper<F>()
-        where F: FnMut()
-    {
-        MAIN_LOOP_CALLBACK.with(|z| {
-            let closure = *z.borrow_mut() as *mut F;
-            unsafe {
-                (*closure)();
-            }
-        });
-    }
-}
-
@@ -0,0 +1,813 @@
+// Browser input and window events from html5.h, handled by rust closures.
+// A closure is kept until it is replaced by another one for the same event
+// and target, or removed with `remove_callback`. Returning true from it
+// prevents the default action of the browser.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::{c_void, c_int, c_char};
+use std::ptr;
+use super::html5::*;
+use super::{c_string, Error};
+
+/// element an event is listened on
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// the default of each event, usually the window
+    Default,
+    Document,
+    Window,
+    Screen,
+    /// css selector, or element id in older emscripten
+    Element(String),
+}
+
+impl Target {
+    fn key(&self) -> String {
+        match *self {
+            Target::Default => "".to_owned(),
+            Target::Document => "#document".to_owned(),
+            Target::Window => "#window".to_owned(),
+            Target::Screen => "#screen".to_owned(),
+            Target::Element(ref s) => s.clone(),
+        }
+    }
+}
+
+/// target as passed to C, the string must outlive the pointer
+struct CTarget(Option<CString>, usize);
+
+impl CTarget {
+    fn new(target: &Target) -> Result<CTarget, Error> {
+        Ok(match *target {
+            Target::Default => CTarget(None, 0),
+            Target::Document => CTarget(None, EMSCRIPTEN_EVENT_TARGET_DOCUMENT),
+            Target::Window => CTarget(None, EMSCRIPTEN_EVENT_TARGET_WINDOW),
+            Target::Screen => CTarget(None, EMSCRIPTEN_EVENT_TARGET_SCREEN),
+            Target::Element(ref s) => CTarget(Some(c_string(s)?), 0),
+        })
+    }
+    fn as_ptr(&self) -> *const c_char {
+        match self.0 {
+            Some(ref s) => s.as_ptr(),
+            None => self.1 as *const c_char,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventType {
+    KeyPress,
+    KeyDown,
+    KeyUp,
+    Click,
+    MouseDown,
+    MouseUp,
+    DblClick,
+    MouseMove,
+    MouseEnter,
+    MouseLeave,
+    MouseOver,
+    MouseOut,
+    Wheel,
+    Resize,
+    Scroll,
+    Blur,
+    Focus,
+    FocusIn,
+    FocusOut,
+    TouchStart,
+    TouchEnd,
+    TouchMove,
+    TouchCancel,
+    VisibilityChange,
+    FullscreenChange,
+    PointerlockChange,
+    OrientationChange,
+}
+
+impl EventType {
+    pub fn code(&self) -> c_int {
+        match *self {
+            EventType::KeyPress => EMSCRIPTEN_EVENT_KEYPRESS,
+            EventType::KeyDown => EMSCRIPTEN_EVENT_KEYDOWN,
+            EventType::KeyUp => EMSCRIPTEN_EVENT_KEYUP,
+            EventType::Click => EMSCRIPTEN_EVENT_CLICK,
+            EventType::MouseDown => EMSCRIPTEN_EVENT_MOUSEDOWN,
+            EventType::MouseUp => EMSCRIPTEN_EVENT_MOUSEUP,
+            EventType::DblClick => EMSCRIPTEN_EVENT_DBLCLICK,
+            EventType::MouseMove => EMSCRIPTEN_EVENT_MOUSEMOVE,
+            EventType::MouseEnter => EMSCRIPTEN_EVENT_MOUSEENTER,
+            EventType::MouseLeave => EMSCRIPTEN_EVENT_MOUSELEAVE,
+            EventType::MouseOver => EMSCRIPTEN_EVENT_MOUSEOVER,
+            EventType::MouseOut => EMSCRIPTEN_EVENT_MOUSEOUT,
+            EventType::Wheel => EMSCRIPTEN_EVENT_WHEEL,
+            EventType::Resize => EMSCRIPTEN_EVENT_RESIZE,
+            EventType::Scroll => EMSCRIPTEN_EVENT_SCROLL,
+            EventType::Blur => EMSCRIPTEN_EVENT_BLUR,
+            EventType::Focus => EMSCRIPTEN_EVENT_FOCUS,
+            EventType::FocusIn => EMSCRIPTEN_EVENT_FOCUSIN,
+            EventType::FocusOut => EMSCRIPTEN_EVENT_FOCUSOUT,
+            EventType::TouchStart => EMSCRIPTEN_EVENT_TOUCHSTART,
+            EventType::TouchEnd => EMSCRIPTEN_EVENT_TOUCHEND,
+            EventType::TouchMove => EMSCRIPTEN_EVENT_TOUCHMOVE,
+            EventType::TouchCancel => EMSCRIPTEN_EVENT_TOUCHCANCEL,
+            EventType::VisibilityChange => EMSCRIPTEN_EVENT_VISIBILITYCHANGE,
+            EventType::FullscreenChange => EMSCRIPTEN_EVENT_FULLSCREENCHANGE,
+            EventType::PointerlockChange => EMSCRIPTEN_EVENT_POINTERLOCKCHANGE,
+            EventType::OrientationChange => EMSCRIPTEN_EVENT_ORIENTATIONCHANGE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl Modifiers {
+    fn new(ctrl: EM_BOOL, shift: EM_BOOL, alt: EM_BOOL, meta: EM_BOOL) -> Modifiers {
+        Modifiers { ctrl: ctrl != 0, shift: shift != 0, alt: alt != 0, meta: meta != 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardEvent {
+    /// like "a" or "Enter"
+    pub key: String,
+    /// physical key, like "KeyA"
+    pub code: String,
+    pub location: u32,
+    pub modifiers: Modifiers,
+    pub repeat: bool,
+    pub locale: String,
+    pub char_value: String,
+    pub char_code: u32,
+    pub key_code: u32,
+    pub which: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseEvent {
+    pub timestamp: f64,
+    pub screen_x: i32,
+    pub screen_y: i32,
+    pub client_x: i32,
+    pub client_y: i32,
+    pub modifiers: Modifiers,
+    /// 0 left, 1 middle, 2 right
+    pub button: u16,
+    /// pressed buttons, bit 0 left, 1 right, 2 middle
+    pub buttons: u16,
+    pub movement_x: i32,
+    pub movement_y: i32,
+    /// relative to the target element
+    pub target_x: i32,
+    pub target_y: i32,
+    /// relative to the canvas
+    pub canvas_x: i32,
+    pub canvas_y: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaMode {
+    Pixel,
+    Line,
+    Page,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelEvent {
+    pub mouse: MouseEvent,
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub delta_z: f64,
+    pub delta_mode: DeltaMode,
+}
+
+/// resize and scroll
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiEvent {
+    pub detail: i32,
+    pub document_body_client_width: i32,
+    pub document_body_client_height: i32,
+    pub window_inner_width: i32,
+    pub window_inner_height: i32,
+    pub window_outer_width: i32,
+    pub window_outer_height: i32,
+    pub scroll_top: i32,
+    pub scroll_left: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusEvent {
+    pub node_name: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    pub identifier: i32,
+    pub screen_x: i32,
+    pub screen_y: i32,
+    pub client_x: i32,
+    pub client_y: i32,
+    pub page_x: i32,
+    pub page_y: i32,
+    /// changed by this event
+    pub is_changed: bool,
+    pub on_target: bool,
+    pub target_x: i32,
+    pub target_y: i32,
+    pub canvas_x: i32,
+    pub canvas_y: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchEvent {
+    pub touches: Vec<TouchPoint>,
+    pub modifiers: Modifiers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityState {
+    Hidden,
+    Visible,
+    Prerender,
+    Unloaded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VisibilityChangeEvent {
+    pub hidden: bool,
+    pub visibility_state: VisibilityState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullscreenChangeEvent {
+    pub is_fullscreen: bool,
+    /// the browser allows fullscreen
+    pub fullscreen_enabled: bool,
+    pub node_name: String,
+    pub id: String,
+    pub element_width: i32,
+    pub element_height: i32,
+    pub screen_width: i32,
+    pub screen_height: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointerlockChangeEvent {
+    pub is_active: bool,
+    pub node_name: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    PortraitPrimary,
+    PortraitSecondary,
+    LandscapePrimary,
+    LandscapeSecondary,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientationChangeEvent {
+    pub orientation: Orientation,
+    /// degrees from the natural orientation
+    pub angle: i32,
+}
+
+/// nul terminated utf8 in a fixed size buffer
+fn utf8(buf: &[c_char]) -> String {
+    let bytes: Vec<u8> = buf.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+impl From<&EmscriptenKeyboardEvent> for KeyboardEvent {
+    fn from(e: &EmscriptenKeyboardEvent) -> KeyboardEvent {
+        KeyboardEvent {
+            key: utf8(&e.key),
+            code: utf8(&e.code),
+            location: e.location as u32,
+            modifiers: Modifiers::new(e.ctrlKey, e.shiftKey, e.altKey, e.metaKey),
+            repeat: e.repeat != 0,
+            locale: utf8(&e.locale),
+            char_value: utf8(&e.charValue),
+            char_code: e.charCode as u32,
+            key_code: e.keyCode as u32,
+            which: e.which as u32,
+        }
+    }
+}
+
+impl From<&EmscriptenMouseEvent> for MouseEvent {
+    fn from(e: &EmscriptenMouseEvent) -> MouseEvent {
+        MouseEvent {
+            timestamp: e.timestamp,
+            screen_x: e.screenX as i32,
+            screen_y: e.screenY as i32,
+            client_x: e.clientX as i32,
+            client_y: e.clientY as i32,
+            modifiers: Modifiers::new(e.ctrlKey, e.shiftKey, e.altKey, e.metaKey),
+            button: e.button,
+            buttons: e.buttons,
+            movement_x: e.movementX as i32,
+            movement_y: e.movementY as i32,
+            target_x: e.targetX as i32,
+            target_y: e.targetY as i32,
+            canvas_x: e.canvasX as i32,
+            canvas_y: e.canvasY as i32,
+        }
+    }
+}
+
+impl From<&EmscriptenWheelEvent> for WheelEvent {
+    fn from(e: &EmscriptenWheelEvent) -> WheelEvent {
+        WheelEvent {
+            mouse: MouseEvent::from(&e.mouse),
+            delta_x: e.deltaX,
+            delta_y: e.deltaY,
+            delta_z: e.deltaZ,
+            delta_mode: match e.deltaMode {
+                1 => DeltaMode::Line,
+                2 => DeltaMode::Page,
+                _ => DeltaMode::Pixel,
+            },
+        }
+    }
+}
+
+impl From<&EmscriptenUiEvent> for UiEvent {
+    fn from(e: &EmscriptenUiEvent) -> UiEvent {
+        UiEvent {
+            detail: e.detail as i32,
+            document_body_client_width: e.documentBodyClientWidth,
+            document_body_client_height: e.documentBodyClientHeight,
+            window_inner_width: e.windowInnerWidth,
+            window_inner_height: e.windowInnerHeight,
+            window_outer_width: e.windowOuterWidth,
+            window_outer_height: e.windowOuterHeight,
+            scroll_top: e.scrollTop,
+            scroll_left: e.scrollLeft,
+        }
+    }
+}
+
+impl From<&EmscriptenFocusEvent> for FocusEvent {
+    fn from(e: &EmscriptenFocusEvent) -> FocusEvent {
+        FocusEvent { node_name: utf8(&e.nodeName), id: utf8(&e.id) }
+    }
+}
+
+impl From<&EmscriptenTouchPoint> for TouchPoint {
+    fn from(t: &EmscriptenTouchPoint) -> TouchPoint {
+        TouchPoint {
+            identifier: t.identifier as i32,
+            screen_x: t.screenX as i32,
+            screen_y: t.screenY as i32,
+            client_x: t.clientX as i32,
+            client_y: t.clientY as i32,
+            page_x: t.pageX as i32,
+            page_y: t.pageY as i32,
+            is_changed: t.isChanged != 0,
+            on_target: t.onTarget != 0,
+            target_x: t.targetX as i32,
+            target_y: t.targetY as i32,
+            canvas_x: t.canvasX as i32,
+            canvas_y: t.canvasY as i32,
+        }
+    }
+}
+
+impl From<&EmscriptenTouchEvent> for TouchEvent {
+    fn from(e: &EmscriptenTouchEvent) -> TouchEvent {
+        let n = (e.numTouches.max(0) as usize).min(e.touches.len());
+        TouchEvent {
+            touches: e.touches[..n].iter().map(TouchPoint::from).collect(),
+            modifiers: Modifiers::new(e.ctrlKey, e.shiftKey, e.altKey, e.metaKey),
+        }
+    }
+}
+
+impl From<&EmscriptenVisibilityChangeEvent> for VisibilityChangeEvent {
+    fn from(e: &EmscriptenVisibilityChangeEvent) -> VisibilityChangeEvent {
+        VisibilityChangeEvent {
+            hidden: e.hidden != 0,
+            visibility_state: match e.visibilityState {
+                EMSCRIPTEN_VISIBILITY_VISIBLE => VisibilityState::Visible,
+                EMSCRIPTEN_VISIBILITY_PRERENDER => VisibilityState::Prerender,
+                EMSCRIPTEN_VISIBILITY_UNLOADED => VisibilityState::Unloaded,
+                _ => VisibilityState::Hidden,
+            },
+        }
+    }
+}
+
+impl From<&EmscriptenFullscreenChangeEvent> for FullscreenChangeEvent {
+    fn from(e: &EmscriptenFullscreenChangeEvent) -> FullscreenChangeEvent {
+        FullscreenChangeEvent {
+            is_fullscreen: e.isFullscreen != 0,
+            fullscreen_enabled: e.fullscreenEnabled != 0,
+            node_name: utf8(&e.nodeName),
+            id: utf8(&e.id),
+            element_width: e.elementWidth,
+            element_height: e.elementHeight,
+            screen_width: e.screenWidth,
+            screen_height: e.screenHeight,
+        }
+    }
+}
+
+impl From<&EmscriptenPointerlockChangeEvent> for PointerlockChangeEvent {
+    fn from(e: &EmscriptenPointerlockChangeEvent) -> PointerlockChangeEvent {
+        PointerlockChangeEvent { is_active: e.isActive != 0, node_name: utf8(&e.nodeName), id: utf8(&e.id) }
+    }
+}
+
+impl From<&EmscriptenOrientationChangeEvent> for OrientationChangeEvent {
+    fn from(e: &EmscriptenOrientationChangeEvent) -> OrientationChangeEvent {
+        OrientationChangeEvent {
+            orientation: match e.orientationIndex {
+                EMSCRIPTEN_ORIENTATION_PORTRAIT_PRIMARY => Orientation::PortraitPrimary,
+                EMSCRIPTEN_ORIENTATION_PORTRAIT_SECONDARY => Orientation::PortraitSecondary,
+                EMSCRIPTEN_ORIENTATION_LANDSCAPE_PRIMARY => Orientation::LandscapePrimary,
+                EMSCRIPTEN_ORIENTATION_LANDSCAPE_SECONDARY => Orientation::LandscapeSecondary,
+                _ => Orientation::Unknown,
+            },
+            angle: e.orientationAngle,
+        }
+    }
+}
+
+/// gets the raw event struct
+type Handler = Box<FnMut(*const c_void) -> bool>;
+
+struct Slot {
+    event_type: EventType,
+    target: String,
+    /// taken out while it runs
+    handler: Option<Handler>,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: usize,
+    slots: HashMap<usize, Slot>,
+}
+
+// the C side gets the slot id as user data
+thread_local!(static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default()));
+
+fn handler<R, E, F>(mut cbk: F) -> Handler
+    where E: for<'a> From<&'a R>, F: FnMut(&E) -> bool + 'static {
+    Box::new(move |raw| cbk(&E::from(unsafe { &*(raw as *const R) })))
+}
+
+extern "C" fn trampoline<R>(_event_type: c_int, event: *const R, user_data: *mut c_void) -> EM_BOOL {
+    let id = user_data as usize;
+    let handler = REGISTRY.with(|r| r.borrow_mut().slots.get_mut(&id).and_then(|s| s.handler.take()));
+    let handled = match handler {
+        Some(mut handler) => {
+            let handled = handler(event as *const c_void);
+            // unless the callback replaced or removed itself
+            REGISTRY.with(|r| if let Some(s) = r.borrow_mut().slots.get_mut(&id) {
+                if s.handler.is_none() {
+                    s.handler = Some(handler);
+                }
+            });
+            handled
+        },
+        None => false,
+    };
+    if handled { EM_TRUE } else { EM_FALSE }
+}
+
+fn result_name(result: EMSCRIPTEN_RESULT) -> &'static str {
+    match result {
+        EMSCRIPTEN_RESULT_NOT_SUPPORTED => "not supported",
+        EMSCRIPTEN_RESULT_FAILED_NOT_DEFERRED => "failed, not deferred",
+        EMSCRIPTEN_RESULT_INVALID_TARGET => "invalid target",
+        EMSCRIPTEN_RESULT_UNKNOWN_TARGET => "unknown target",
+        EMSCRIPTEN_RESULT_INVALID_PARAM => "invalid param",
+        EMSCRIPTEN_RESULT_NO_DATA => "no data",
+        _ => "failed",
+    }
+}
+
+/// registers `handler` through `set`, which gets the target and the user data,
+/// a `None` handler removes the registered one
+fn register<S>(event_type: EventType, target: &Target, handler: Option<Handler>, set: S) -> Result<(), Error>
+    where S: FnOnce(*const c_char, *mut c_void) -> EMSCRIPTEN_RESULT {
+    let c_target = CTarget::new(target)?;
+    let key = target.key();
+    let id = REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        r.next_id += 1;
+        r.next_id
+    });
+    let user_data = if handler.is_some() { id as *mut c_void } else { ptr::null_mut() };
+    let result = set(c_target.as_ptr(), user_data);
+    if result < 0 {
+        return Err(Error::Html5 { event: format!("{:?} on {:?}", event_type, target), reason: result_name(result) });
+    }
+    REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        r.slots.retain(|_, s| s.event_type != event_type || s.target != key);
+        if handler.is_some() {
+            r.slots.insert(id, Slot { event_type, target: key, handler });
+        }
+    });
+    Ok(())
+}
+
+fn em_bool(b: bool) -> EM_BOOL {
+    if b { EM_TRUE } else { EM_FALSE }
+}
+
+fn invalid(event_type: EventType) -> Error {
+    Error::Html5 { event: format!("{:?}", event_type), reason: "wrong event type for this callback" }
+}
+
+// the emscripten_set_*_callback functions with a target, spelled out
+// per callback type so the fn pointer in the Option is known to be FFI-safe
+type KeySetter = unsafe extern "C" fn(*const c_char, *mut c_void, EM_BOOL, Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT;
+type MouseSetter = unsafe extern "C" fn(*const c_char, *mut c_void, EM_BOOL, Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+type UiSetter = unsafe extern "C" fn(*const c_char, *mut c_void, EM_BOOL, Option<em_ui_callback_func>) -> EMSCRIPTEN_RESULT;
+type FocusSetter = unsafe extern "C" fn(*const c_char, *mut c_void, EM_BOOL, Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT;
+type TouchSetter = unsafe extern "C" fn(*const c_char, *mut c_void, EM_BOOL, Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT;
+
+fn key_setter(event_type: EventType) -> Option<KeySetter> {
+    match event_type {
+        EventType::KeyPress => Some(emscripten_set_keypress_callback),
+        EventType::KeyDown => Some(emscripten_set_keydown_callback),
+        EventType::KeyUp => Some(emscripten_set_keyup_callback),
+        _ => None,
+    }
+}
+
+fn mouse_setter(event_type: EventType) -> Option<MouseSetter> {
+    match event_type {
+        EventType::Click => Some(emscripten_set_click_callback),
+        EventType::MouseDown => Some(emscripten_set_mousedown_callback),
+        EventType::MouseUp => Some(emscripten_set_mouseup_callback),
+        EventType::DblClick => Some(emscripten_set_dblclick_callback),
+        EventType::MouseMove => Some(emscripten_set_mousemove_callback),
+        EventType::MouseEnter => Some(emscripten_set_mouseenter_callback),
+        EventType::MouseLeave => Some(emscripten_set_mouseleave_callback),
+        EventType::MouseOver => Some(emscripten_set_mouseover_callback),
+        EventType::MouseOut => Some(emscripten_set_mouseout_callback),
+        _ => None,
+    }
+}
+
+fn ui_setter(event_type: EventType) -> Option<UiSetter> {
+    match event_type {
+        EventType::Resize => Some(emscripten_set_resize_callback),
+        EventType::Scroll => Some(emscripten_set_scroll_callback),
+        _ => None,
+    }
+}
+
+fn focus_setter(event_type: EventType) -> Option<FocusSetter> {
+    match event_type {
+        EventType::Blur => Some(emscripten_set_blur_callback),
+        EventType::Focus => Some(emscripten_set_focus_callback),
+        EventType::FocusIn => Some(emscripten_set_focusin_callback),
+        EventType::FocusOut => Some(emscripten_set_focusout_callback),
+        _ => None,
+    }
+}
+
+fn touch_setter(event_type: EventType) -> Option<TouchSetter> {
+    match event_type {
+        EventType::TouchStart => Some(emscripten_set_touchstart_callback),
+        EventType::TouchEnd => Some(emscripten_set_touchend_callback),
+        EventType::TouchMove => Some(emscripten_set_touchmove_callback),
+        EventType::TouchCancel => Some(emscripten_set_touchcancel_callback),
+        _ => None,
+    }
+}
+
+/// `event_type` is one of `KeyPress`, `KeyDown` or `KeyUp`
+pub fn set_key_callback<F>(event_type: EventType, target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&KeyboardEvent) -> bool + 'static {
+    let set = key_setter(event_type).ok_or(invalid(event_type))?;
+    register(event_type, &target, Some(handler::<EmscriptenKeyboardEvent, _, _>(cbk)), |t, data| unsafe {
+        set(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenKeyboardEvent>))
+    })
+}
+
+/// `event_type` is one of `Click`, `MouseDown`, `MouseUp`, `DblClick`, `MouseMove`,
+/// `MouseEnter`, `MouseLeave`, `MouseOver` or `MouseOut`
+pub fn set_mouse_callback<F>(event_type: EventType, target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&MouseEvent) -> bool + 'static {
+    let set = mouse_setter(event_type).ok_or(invalid(event_type))?;
+    register(event_type, &target, Some(handler::<EmscriptenMouseEvent, _, _>(cbk)), |t, data| unsafe {
+        set(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenMouseEvent>))
+    })
+}
+
+pub fn set_wheel_callback<F>(target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&WheelEvent) -> bool + 'static {
+    register(EventType::Wheel, &target, Some(handler::<EmscriptenWheelEvent, _, _>(cbk)), |t, data| unsafe {
+        emscripten_set_wheel_callback(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenWheelEvent>))
+    })
+}
+
+/// `event_type` is `Resize` or `Scroll`, usually on `Target::Window`
+pub fn set_ui_callback<F>(event_type: EventType, target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&UiEvent) -> bool + 'static {
+    let set = ui_setter(event_type).ok_or(invalid(event_type))?;
+    register(event_type, &target, Some(handler::<EmscriptenUiEvent, _, _>(cbk)), |t, data| unsafe {
+        set(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenUiEvent>))
+    })
+}
+
+/// `event_type` is one of `Blur`, `Focus`, `FocusIn` or `FocusOut`
+pub fn set_focus_callback<F>(event_type: EventType, target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&FocusEvent) -> bool + 'static {
+    let set = focus_setter(event_type).ok_or(invalid(event_type))?;
+    register(event_type, &target, Some(handler::<EmscriptenFocusEvent, _, _>(cbk)), |t, data| unsafe {
+        set(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenFocusEvent>))
+    })
+}
+
+/// `event_type` is one of `TouchStart`, `TouchEnd`, `TouchMove` or `TouchCancel`
+pub fn set_touch_callback<F>(event_type: EventType, target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&TouchEvent) -> bool + 'static {
+    let set = touch_setter(event_type).ok_or(invalid(event_type))?;
+    register(event_type, &target, Some(handler::<EmscriptenTouchEvent, _, _>(cbk)), |t, data| unsafe {
+        set(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenTouchEvent>))
+    })
+}
+
+/// the page got hidden or shown, like switching browser tabs
+pub fn set_visibilitychange_callback<F>(use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&VisibilityChangeEvent) -> bool + 'static {
+    register(EventType::VisibilityChange, &Target::Document, Some(handler::<EmscriptenVisibilityChangeEvent, _, _>(cbk)), |_, data| unsafe {
+        emscripten_set_visibilitychange_callback(data, em_bool(use_capture), Some(trampoline::<EmscriptenVisibilityChangeEvent>))
+    })
+}
+
+pub fn set_fullscreenchange_callback<F>(target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&FullscreenChangeEvent) -> bool + 'static {
+    register(EventType::FullscreenChange, &target, Some(handler::<EmscriptenFullscreenChangeEvent, _, _>(cbk)), |t, data| unsafe {
+        emscripten_set_fullscreenchange_callback(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenFullscreenChangeEvent>))
+    })
+}
+
+pub fn set_pointerlockchange_callback<F>(target: Target, use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&PointerlockChangeEvent) -> bool + 'static {
+    register(EventType::PointerlockChange, &target, Some(handler::<EmscriptenPointerlockChangeEvent, _, _>(cbk)), |t, data| unsafe {
+        emscripten_set_pointerlockchange_callback(t, data, em_bool(use_capture), Some(trampoline::<EmscriptenPointerlockChangeEvent>))
+    })
+}
+
+/// the screen of a phone or tablet turned
+pub fn set_orientationchange_callback<F>(use_capture: bool, cbk: F) -> Result<(), Error>
+    where F: FnMut(&OrientationChangeEvent) -> bool + 'static {
+    register(EventType::OrientationChange, &Target::Screen, Some(handler::<EmscriptenOrientationChangeEvent, _, _>(cbk)), |_, data| unsafe {
+        emscripten_set_orientationchange_callback(data, em_bool(use_capture), Some(trampoline::<EmscriptenOrientationChangeEvent>))
+    })
+}
+
+/// stop listening to `event_type` on `target`, the callback is dropped
+pub fn remove_callback(event_type: EventType, target: Target) -> Result<(), Error> {
+    register(event_type, &target, None, |t, data| unsafe {
+        match event_type {
+            EventType::Wheel => emscripten_set_wheel_callback(t, data, EM_FALSE, None),
+            EventType::VisibilityChange => emscripten_set_visibilitychange_callback(data, EM_FALSE, None),
+            EventType::FullscreenChange => emscripten_set_fullscreenchange_callback(t, data, EM_FALSE, None),
+            EventType::PointerlockChange => emscripten_set_pointerlockchange_callback(t, data, EM_FALSE, None),
+            EventType::OrientationChange => emscripten_set_orientationchange_callback(data, EM_FALSE, None),
+            _ => {
+                if let Some(set) = key_setter(event_type) {
+                    set(t, data, EM_FALSE, None)
+                } else if let Some(set) = mouse_setter(event_type) {
+                    set(t, data, EM_FALSE, None)
+                } else if let Some(set) = ui_setter(event_type) {
+                    set(t, data, EM_FALSE, None)
+                } else if let Some(set) = focus_setter(event_type) {
+                    set(t, data, EM_FALSE, None)
+                } else if let Some(set) = touch_setter(event_type) {
+                    set(t, data, EM_FALSE, None)
+                } else {
+                    EMSCRIPTEN_RESULT_INVALID_PARAM
+                }
+            },
+        }
+    })
+}
+
+/// css size of the element at `target`, a css selector
+pub fn get_element_css_size(target: &str) -> Result<(f64, f64), Error> {
+    let c_target = c_string(target)?;
+    let mut w = 0.;
+    let mut h = 0.;
+    let result = unsafe { emscripten_get_element_css_size(c_target.as_ptr(), &mut w, &mut h) };
+    if result < 0 {
+        return Err(Error::Html5 { event: format!("css size of {}", target), reason: result_name(result) });
+    }
+    Ok((w, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+    use std::rc::Rc;
+
+    fn c_chars(s: &str) -> [c_char; 32] {
+        let mut buf = [0; 32];
+        for (b, c) in s.bytes().zip(buf.iter_mut()) {
+            *c = b as c_char;
+        }
+        buf
+    }
+
+    #[test]
+    fn key_and_resize_callbacks() {
+        reset();
+        let keys = Rc::new(RefCell::new(vec![]));
+        let k = keys.clone();
+        set_key_callback(EventType::KeyDown, Target::Window, true, move |e| {
+            k.borrow_mut().push(e.key.clone());
+            e.modifiers.ctrl
+        }).unwrap();
+
+        let mut e: EmscriptenKeyboardEvent = unsafe { mem::zeroed() };
+        e.key = c_chars("Enter");
+        assert_eq!(dispatch(EMSCRIPTEN_EVENT_KEYDOWN, "#window", &e), Some(EM_FALSE));
+        e.key = c_chars("s");
+        e.ctrlKey = EM_TRUE;
+        assert_eq!(dispatch(EMSCRIPTEN_EVENT_KEYDOWN, "#window", &e), Some(EM_TRUE));
+        assert_eq!(*keys.borrow(), vec!["Enter", "s"]);
+
+        let size = Rc::new(RefCell::new((0, 0)));
+        let s = size.clone();
+        set_ui_callback(EventType::Resize, Target::Window, false, move |e| {
+            *s.borrow_mut() = (e.window_inner_width, e.window_inner_height);
+            false
+        }).unwrap();
+        let mut ui: EmscriptenUiEvent = unsafe { mem::zeroed() };
+        ui.windowInnerWidth = 800;
+        ui.windowInnerHeight = 600;
+        dispatch(EMSCRIPTEN_EVENT_RESIZE, "#window", &ui);
+        assert_eq!(*size.borrow(), (800, 600));
+
+        assert!(set_ui_callback(EventType::KeyUp, Target::Window, false, |_| false).is_err());
+        remove_callback(EventType::KeyDown, Target::Window).unwrap();
+        assert!(!is_registered(EMSCRIPTEN_EVENT_KEYDOWN, "#window"));
+        REGISTRY.with(|r| assert_eq!(r.borrow().slots.len(), 1));
+    }
+
+    #[test]
+    fn replace_from_inside_callback() {
+        reset();
+        let calls = Rc::new(RefCell::new(vec![]));
+        let c = calls.clone();
+        set_visibilitychange_callback(false, move |e| {
+            c.borrow_mut().push(("first", e.visibility_state));
+            let c = c.clone();
+            set_visibilitychange_callback(false, move |e| {
+                c.borrow_mut().push(("second", e.visibility_state));
+                false
+            }).unwrap();
+            false
+        }).unwrap();
+
+        let e = EmscriptenVisibilityChangeEvent { hidden: EM_TRUE, visibilityState: EMSCRIPTEN_VISIBILITY_HIDDEN };
+        dispatch(EMSCRIPTEN_EVENT_VISIBILITYCHANGE, "#document", &e);
+        let e = EmscriptenVisibilityChangeEvent { hidden: EM_FALSE, visibilityState: EMSCRIPTEN_VISIBILITY_VISIBLE };
+        dispatch(EMSCRIPTEN_EVENT_VISIBILITYCHANGE, "#document", &e);
+        assert_eq!(*calls.borrow(), vec![("first", VisibilityState::Hidden), ("second", VisibilityState::Visible)]);
+        REGISTRY.with(|r| assert_eq!(r.borrow().slots.len(), 1));
+    }
+
+    #[test]
+    fn touch_points() {
+        reset();
+        let touches = Rc::new(RefCell::new(vec![]));
+        let t = touches.clone();
+        set_touch_callback(EventType::TouchStart, Target::Element("#canvas".to_owned()), false, move |e| {
+            *t.borrow_mut() = e.touches.iter().map(|p| (p.identifier, p.canvas_x, p.canvas_y)).collect();
+            true
+        }).unwrap();
+
+        let mut e: EmscriptenTouchEvent = unsafe { mem::zeroed() };
+        e.numTouches = 2;
+        e.touches[0].identifier = 7;
+        e.touches[0].canvasX = 10;
+        e.touches[1].identifier = 8;
+        e.touches[1].canvasY = 20;
+        assert_eq!(dispatch(EMSCRIPTEN_EVENT_TOUCHSTART, "#canvas", &e), Some(EM_TRUE));
+        assert_eq!(*touches.borrow(), vec![(7, 10, 0), (8, 0, 20)]);
+        assert_eq!(get_element_css_size("#canvas"),
+                   Err(Error::Html5 { event: "css size of #canvas".to_owned(), reason: "not supported" }));
+    }
+}
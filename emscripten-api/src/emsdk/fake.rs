@@ -0,0 +1,290 @@
+// Pure rust stand-in for the emscripten functions, used natively.
+// Async work (wget, preload, async_call) is queued like in the browser and
+// runs when a test calls `advance`, main loop frames run with `run_frame`.
+// Urls answer with what was registered by `serve`, files go to an in-memory FS.
+// State is per thread, so each test has its own.
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_void, c_int, c_uint, c_double, c_char};
+use std::ptr;
+
+pub type em_callback_func = extern "C" fn();
+pub type em_arg_callback_func = extern "C" fn(*mut c_void);
+pub type em_str_callback_func = extern "C" fn(*const c_char);
+pub type em_async_wget_onload_func = extern "C" fn(*mut c_void, *mut c_void, c_int);
+pub type em_async_wget2_onload_func = extern "C" fn(c_uint, *mut c_void, *const c_char);
+pub type em_async_wget2_onstatus_func = extern "C" fn(c_uint, *mut c_void, c_int);
+pub type em_run_preload_plugins_data_onload_func = extern "C" fn(*mut c_void, *const c_char);
+
+/// file types the browser preload plugins decode
+const PRELOAD_EXTS: [&str; 8] = ["png", "jpg", "jpeg", "bmp", "gif", "ogg", "wav", "mp3"];
+
+struct MainLoop {
+    func: em_callback_func,
+    fps: c_int,
+    paused: bool,
+}
+
+struct Task {
+    due: f64,
+    run: Box<FnOnce()>,
+}
+
+#[derive(Default)]
+struct Fake {
+    now: f64,
+    served: HashMap<String, Vec<u8>>,
+    fs: HashMap<String, Vec<u8>>,
+    tasks: Vec<Task>,
+    main_loop: Option<MainLoop>,
+    requests: c_uint,
+    scripts: Vec<String>,
+    script_results: HashMap<String, String>,
+    /// keeps the last run_script_string result alive, like emscripten does
+    script_string: Option<CString>,
+}
+
+thread_local!(static FAKE: RefCell<Fake> = RefCell::new(Fake::default()));
+
+fn with<T, F: FnOnce(&mut Fake) -> T>(f: F) -> T {
+    FAKE.with(|fake| f(&mut fake.borrow_mut()))
+}
+
+fn queue<F: FnOnce() + 'static>(delay: f64, f: F) {
+    with(|fake| {
+        let due = fake.now + delay.max(0.);
+        fake.tasks.push(Task { due, run: Box::new(f) });
+    });
+}
+
+unsafe fn to_string(s: *const c_char) -> String {
+    CStr::from_ptr(s).to_string_lossy().into_owned()
+}
+
+fn is_preloadable(file: &str) -> bool {
+    let ext = file.rsplit('.').next().unwrap_or("").to_lowercase();
+    file.contains('.') && PRELOAD_EXTS.contains(&ext.as_str())
+}
+
+// test controls
+
+/// forget all state of this thread
+pub fn reset() {
+    with(|fake| *fake = Fake::default());
+}
+
+/// answer `url` with `data`, other urls fail with 404
+pub fn serve(url: &str, data: &[u8]) {
+    with(|fake| fake.served.insert(url.to_owned(), data.to_vec()));
+}
+
+pub fn write_file(path: &str, data: &[u8]) {
+    with(|fake| fake.fs.insert(path.to_owned(), data.to_vec()));
+}
+
+pub fn read_file(path: &str) -> Option<Vec<u8>> {
+    with(|fake| fake.fs.get(path).cloned())
+}
+
+/// value returned when `script` is run
+pub fn set_script_result(script: &str, value: &str) {
+    with(|fake| fake.script_results.insert(script.to_owned(), value.to_owned()));
+}
+
+/// scripts run so far
+pub fn scripts() -> Vec<String> {
+    with(|fake| fake.scripts.clone())
+}
+
+/// move time forward by `ms`, running the queued work that is due
+pub fn advance(ms: f64) {
+    let end = with(|fake| fake.now + ms);
+    loop {
+        // tasks may queue more tasks
+        let task = with(|fake| {
+            let next = fake.tasks.iter().enumerate()
+                .filter(|&(_, t)| t.due <= end)
+                .min_by(|a, b| a.1.due.partial_cmp(&b.1.due).unwrap())
+                .map(|(i, _)| i);
+            next.map(|i| {
+                let t = fake.tasks.remove(i);
+                fake.now = fake.now.max(t.due);
+                t
+            })
+        });
+        match task {
+            Some(t) => (t.run)(),
+            None => break,
+        }
+    }
+    with(|fake| fake.now = end);
+}
+
+/// run one main loop frame, false if there is no loop or it is paused
+pub fn run_frame() -> bool {
+    let func = with(|fake| match fake.main_loop {
+        Some(ref l) if !l.paused => Some(l.func),
+        _ => None,
+    });
+    match func {
+        Some(func) => {
+            func();
+            true
+        },
+        None => false,
+    }
+}
+
+pub fn main_loop_fps() -> Option<c_int> {
+    with(|fake| fake.main_loop.as_ref().map(|l| l.fps))
+}
+
+// emscripten.h
+
+/// unlike emscripten, returns even if `simulate_infinite_loop` is set
+pub unsafe fn emscripten_set_main_loop(func: em_callback_func, fps: c_int, _simulate_infinite_loop: c_int) {
+    with(|fake| {
+        assert!(fake.main_loop.is_none(), "emscripten_set_main_loop: there can only be one main loop function at once");
+        fake.main_loop = Some(MainLoop { func, fps, paused: false });
+    });
+}
+
+pub unsafe fn emscripten_cancel_main_loop() {
+    with(|fake| fake.main_loop = None);
+}
+
+pub unsafe fn emscripten_pause_main_loop() {
+    with(|fake| if let Some(ref mut l) = fake.main_loop {
+        l.paused = true;
+    });
+}
+
+pub unsafe fn emscripten_resume_main_loop() {
+    with(|fake| if let Some(ref mut l) = fake.main_loop {
+        l.paused = false;
+    });
+}
+
+pub unsafe fn emscripten_get_now() -> c_double {
+    with(|fake| fake.now)
+}
+
+pub unsafe fn emscripten_async_call(func: em_arg_callback_func, arg: *mut c_void, millis: c_int) {
+    let arg = arg as usize;
+    queue(millis as f64, move || func(arg as *mut c_void));
+}
+
+pub unsafe fn emscripten_run_preload_plugins(file: *const c_char,
+                                             onload: em_str_callback_func,
+                                             onerror: em_str_callback_func) -> c_int {
+    let file = to_string(file);
+    if !is_preloadable(&file) {
+        return -1;
+    }
+    let exists = with(|fake| fake.fs.contains_key(&file));
+    queue(0., move || {
+        let name = CString::new(file).unwrap();
+        if exists {
+            onload(name.as_ptr());
+        } else {
+            onerror(name.as_ptr());
+        }
+    });
+    0
+}
+
+pub unsafe fn emscripten_run_preload_plugins_data(data: *const c_char,
+                                                  size: c_int,
+                                                  suffix: *const c_char,
+                                                  arg: *mut c_void,
+                                                  onload: em_run_preload_plugins_data_onload_func,
+                                                  onerror: em_arg_callback_func) {
+    let suffix = to_string(suffix);
+    let data = ::std::slice::from_raw_parts(data as *const u8, size as usize).to_vec();
+    let arg = arg as usize;
+    queue(0., move || {
+        let name = format!("prerun_data_{}.{}", data.len(), suffix);
+        if is_preloadable(&name) {
+            write_file(&name, &data);
+            let name = CString::new(name).unwrap();
+            onload(arg as *mut c_void, name.as_ptr());
+        } else {
+            onerror(arg as *mut c_void);
+        }
+    });
+}
+
+pub unsafe fn emscripten_run_script(script: *const c_char) {
+    let script = to_string(script);
+    with(|fake| fake.scripts.push(script));
+}
+
+pub unsafe fn emscripten_run_script_int(script: *const c_char) -> c_int {
+    let script = to_string(script);
+    with(|fake| {
+        fake.scripts.push(script.clone());
+        fake.script_results.get(&script).and_then(|v| v.parse().ok()).unwrap_or(0)
+    })
+}
+
+pub unsafe fn emscripten_run_script_string(script: *const c_char) -> *const c_char {
+    let script = to_string(script);
+    with(|fake| {
+        fake.scripts.push(script.clone());
+        fake.script_string = fake.script_results.get(&script).map(|v| CString::new(v.as_str()).unwrap());
+        fake.script_string.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null())
+    })
+}
+
+pub unsafe fn emscripten_exit_with_live_runtime() {}
+
+pub unsafe fn emscripten_async_wget_data(url: *const c_char,
+                                         arg: *mut c_void,
+                                         onload: em_async_wget_onload_func,
+                                         onerror: em_arg_callback_func) {
+    let url = to_string(url);
+    let arg = arg as usize;
+    queue(0., move || {
+        match with(|fake| fake.served.get(&url).cloned()) {
+            Some(mut data) => onload(arg as *mut c_void, data.as_mut_ptr() as *mut c_void, data.len() as c_int),
+            None => onerror(arg as *mut c_void),
+        }
+    });
+}
+
+// mirrors the C signature
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn emscripten_async_wget2(url: *const c_char,
+                                     file: *const c_char,
+                                     _requesttype: *const c_char,
+                                     _param: *const c_char,
+                                     arg: *mut c_void,
+                                     onload: em_async_wget2_onload_func,
+                                     onerror: em_async_wget2_onstatus_func,
+                                     onprogress: Option<em_async_wget2_onstatus_func>) -> c_int {
+    let url = to_string(url);
+    let file = to_string(file);
+    let arg = arg as usize;
+    let handle = with(|fake| {
+        fake.requests += 1;
+        fake.requests
+    });
+    queue(0., move || {
+        match with(|fake| fake.served.get(&url).cloned()) {
+            Some(data) => {
+                if let Some(onprogress) = onprogress {
+                    onprogress(handle, arg as *mut c_void, 100);
+                }
+                write_file(&file, &data);
+                let file = CString::new(file).unwrap();
+                onload(handle, arg as *mut c_void, file.as_ptr());
+            },
+            None => onerror(handle, arg as *mut c_void, 404),
+        }
+    });
+    handle as c_int
+}
@@ -1,8 +1,260 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
 
-use std::os::raw::{c_void, c_int, c_float, c_char, c_double};
+use std::os::raw::{c_void, c_int, c_long, c_ulong, c_ushort, c_char, c_double};
+
+pub type EM_BOOL = c_int;
+pub type EMSCRIPTEN_RESULT = c_int;
+
+pub const EM_TRUE: EM_BOOL = 1;
+pub const EM_FALSE: EM_BOOL = 0;
+
+pub const EMSCRIPTEN_RESULT_SUCCESS: EMSCRIPTEN_RESULT = 0;
+pub const EMSCRIPTEN_RESULT_DEFERRED: EMSCRIPTEN_RESULT = 1;
+pub const EMSCRIPTEN_RESULT_NOT_SUPPORTED: EMSCRIPTEN_RESULT = -1;
+pub const EMSCRIPTEN_RESULT_FAILED_NOT_DEFERRED: EMSCRIPTEN_RESULT = -2;
+pub const EMSCRIPTEN_RESULT_INVALID_TARGET: EMSCRIPTEN_RESULT = -3;
+pub const EMSCRIPTEN_RESULT_UNKNOWN_TARGET: EMSCRIPTEN_RESULT = -4;
+pub const EMSCRIPTEN_RESULT_INVALID_PARAM: EMSCRIPTEN_RESULT = -5;
+pub const EMSCRIPTEN_RESULT_FAILED: EMSCRIPTEN_RESULT = -6;
+pub const EMSCRIPTEN_RESULT_NO_DATA: EMSCRIPTEN_RESULT = -7;
+
+// special targets, passed in place of a css selector
+pub const EMSCRIPTEN_EVENT_TARGET_DOCUMENT: usize = 1;
+pub const EMSCRIPTEN_EVENT_TARGET_WINDOW: usize = 2;
+pub const EMSCRIPTEN_EVENT_TARGET_SCREEN: usize = 3;
+
+pub const EMSCRIPTEN_EVENT_KEYPRESS: c_int = 1;
+pub const EMSCRIPTEN_EVENT_KEYDOWN: c_int = 2;
+pub const EMSCRIPTEN_EVENT_KEYUP: c_int = 3;
+pub const EMSCRIPTEN_EVENT_CLICK: c_int = 4;
+pub const EMSCRIPTEN_EVENT_MOUSEDOWN: c_int = 5;
+pub const EMSCRIPTEN_EVENT_MOUSEUP: c_int = 6;
+pub const EMSCRIPTEN_EVENT_DBLCLICK: c_int = 7;
+pub const EMSCRIPTEN_EVENT_MOUSEMOVE: c_int = 8;
+pub const EMSCRIPTEN_EVENT_WHEEL: c_int = 9;
+pub const EMSCRIPTEN_EVENT_RESIZE: c_int = 10;
+pub const EMSCRIPTEN_EVENT_SCROLL: c_int = 11;
+pub const EMSCRIPTEN_EVENT_BLUR: c_int = 12;
+pub const EMSCRIPTEN_EVENT_FOCUS: c_int = 13;
+pub const EMSCRIPTEN_EVENT_FOCUSIN: c_int = 14;
+pub const EMSCRIPTEN_EVENT_FOCUSOUT: c_int = 15;
+pub const EMSCRIPTEN_EVENT_ORIENTATIONCHANGE: c_int = 18;
+pub const EMSCRIPTEN_EVENT_FULLSCREENCHANGE: c_int = 19;
+pub const EMSCRIPTEN_EVENT_POINTERLOCKCHANGE: c_int = 20;
+pub const EMSCRIPTEN_EVENT_VISIBILITYCHANGE: c_int = 21;
+pub const EMSCRIPTEN_EVENT_TOUCHSTART: c_int = 22;
+pub const EMSCRIPTEN_EVENT_TOUCHEND: c_int = 23;
+pub const EMSCRIPTEN_EVENT_TOUCHMOVE: c_int = 24;
+pub const EMSCRIPTEN_EVENT_TOUCHCANCEL: c_int = 25;
+pub const EMSCRIPTEN_EVENT_MOUSEENTER: c_int = 33;
+pub const EMSCRIPTEN_EVENT_MOUSELEAVE: c_int = 34;
+pub const EMSCRIPTEN_EVENT_MOUSEOVER: c_int = 35;
+pub const EMSCRIPTEN_EVENT_MOUSEOUT: c_int = 36;
+
+pub const EMSCRIPTEN_VISIBILITY_HIDDEN: c_int = 0;
+pub const EMSCRIPTEN_VISIBILITY_VISIBLE: c_int = 1;
+pub const EMSCRIPTEN_VISIBILITY_PRERENDER: c_int = 2;
+pub const EMSCRIPTEN_VISIBILITY_UNLOADED: c_int = 3;
+
+pub const EMSCRIPTEN_ORIENTATION_PORTRAIT_PRIMARY: c_int = 1;
+pub const EMSCRIPTEN_ORIENTATION_PORTRAIT_SECONDARY: c_int = 2;
+pub const EMSCRIPTEN_ORIENTATION_LANDSCAPE_PRIMARY: c_int = 4;
+pub const EMSCRIPTEN_ORIENTATION_LANDSCAPE_SECONDARY: c_int = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenKeyboardEvent {
+    pub key: [c_char; 32],
+    pub code: [c_char; 32],
+    pub location: c_ulong,
+    pub ctrlKey: EM_BOOL,
+    pub shiftKey: EM_BOOL,
+    pub altKey: EM_BOOL,
+    pub metaKey: EM_BOOL,
+    pub repeat: EM_BOOL,
+    pub locale: [c_char; 32],
+    pub charValue: [c_char; 32],
+    pub charCode: c_ulong,
+    pub keyCode: c_ulong,
+    pub which: c_ulong,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenMouseEvent {
+    pub timestamp: c_double,
+    pub screenX: c_long,
+    pub screenY: c_long,
+    pub clientX: c_long,
+    pub clientY: c_long,
+    pub ctrlKey: EM_BOOL,
+    pub shiftKey: EM_BOOL,
+    pub altKey: EM_BOOL,
+    pub metaKey: EM_BOOL,
+    pub button: c_ushort,
+    pub buttons: c_ushort,
+    pub movementX: c_long,
+    pub movementY: c_long,
+    pub targetX: c_long,
+    pub targetY: c_long,
+    pub canvasX: c_long,
+    pub canvasY: c_long,
+    pub padding: c_long,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenWheelEvent {
+    pub mouse: EmscriptenMouseEvent,
+    pub deltaX: c_double,
+    pub deltaY: c_double,
+    pub deltaZ: c_double,
+    pub deltaMode: c_ulong,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenUiEvent {
+    pub detail: c_long,
+    pub documentBodyClientWidth: c_int,
+    pub documentBodyClientHeight: c_int,
+    pub windowInnerWidth: c_int,
+    pub windowInnerHeight: c_int,
+    pub windowOuterWidth: c_int,
+    pub windowOuterHeight: c_int,
+    pub scrollTop: c_int,
+    pub scrollLeft: c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenFocusEvent {
+    pub nodeName: [c_char; 128],
+    pub id: [c_char; 128],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenTouchPoint {
+    pub identifier: c_long,
+    pub screenX: c_long,
+    pub screenY: c_long,
+    pub clientX: c_long,
+    pub clientY: c_long,
+    pub pageX: c_long,
+    pub pageY: c_long,
+    pub isChanged: EM_BOOL,
+    pub onTarget: EM_BOOL,
+    pub targetX: c_long,
+    pub targetY: c_long,
+    pub canvasX: c_long,
+    pub canvasY: c_long,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenTouchEvent {
+    pub numTouches: c_int,
+    pub ctrlKey: EM_BOOL,
+    pub shiftKey: EM_BOOL,
+    pub altKey: EM_BOOL,
+    pub metaKey: EM_BOOL,
+    pub touches: [EmscriptenTouchPoint; 32],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenVisibilityChangeEvent {
+    pub hidden: EM_BOOL,
+    pub visibilityState: c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenFullscreenChangeEvent {
+    pub isFullscreen: EM_BOOL,
+    pub fullscreenEnabled: EM_BOOL,
+    pub nodeName: [c_char; 128],
+    pub id: [c_char; 128],
+    pub elementWidth: c_int,
+    pub elementHeight: c_int,
+    pub screenWidth: c_int,
+    pub screenHeight: c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenPointerlockChangeEvent {
+    pub isActive: EM_BOOL,
+    pub nodeName: [c_char; 128],
+    pub id: [c_char; 128],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EmscriptenOrientationChangeEvent {
+    pub orientationIndex: c_int,
+    pub orientationAngle: c_int,
+}
+
+/// return `EM_TRUE` to prevent the default action of the browser
+pub type em_key_callback_func = extern "C" fn(c_int, *const EmscriptenKeyboardEvent, *mut c_void) -> EM_BOOL;
+pub type em_mouse_callback_func = extern "C" fn(c_int, *const EmscriptenMouseEvent, *mut c_void) -> EM_BOOL;
+pub type em_wheel_callback_func = extern "C" fn(c_int, *const EmscriptenWheelEvent, *mut c_void) -> EM_BOOL;
+pub type em_ui_callback_func = extern "C" fn(c_int, *const EmscriptenUiEvent, *mut c_void) -> EM_BOOL;
+pub type em_focus_callback_func = extern "C" fn(c_int, *const EmscriptenFocusEvent, *mut c_void) -> EM_BOOL;
+pub type em_touch_callback_func = extern "C" fn(c_int, *const EmscriptenTouchEvent, *mut c_void) -> EM_BOOL;
+pub type em_visibilitychange_callback_func = extern "C" fn(c_int, *const EmscriptenVisibilityChangeEvent, *mut c_void) -> EM_BOOL;
+pub type em_fullscreenchange_callback_func = extern "C" fn(c_int, *const EmscriptenFullscreenChangeEvent, *mut c_void) -> EM_BOOL;
+pub type em_pointerlockchange_callback_func = extern "C" fn(c_int, *const EmscriptenPointerlockChangeEvent, *mut c_void) -> EM_BOOL;
+pub type em_orientationchange_callback_func = extern "C" fn(c_int, *const EmscriptenOrientationChangeEvent, *mut c_void) -> EM_BOOL;
+
+// a `None` callback removes the one registered for the target
+
+#[cfg(target_os = "emscripten")]
 extern "C" {
-    pub fn emscripten_set_element_css_size(target: *const c_char, width: c_double, height: c_double) -> c_int;
-    pub fn emscripten_get_element_css_size(target: *const c_char, width: *mut c_double, height: *mut c_double) -> c_int;
+    pub fn emscripten_set_element_css_size(target: *const c_char, width: c_double, height: c_double) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_get_element_css_size(target: *const c_char, width: *mut c_double, height: *mut c_double) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_keypress_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_keydown_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_keyup_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_click_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mousedown_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mouseup_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_dblclick_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mousemove_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mouseenter_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mouseleave_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mouseover_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_mouseout_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_wheel_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_wheel_callback_func>) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_resize_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_ui_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_scroll_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_ui_callback_func>) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_blur_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_focus_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_focusin_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_focusout_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_touchstart_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_touchend_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_touchmove_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_touchcancel_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT;
+
+    pub fn emscripten_set_visibilitychange_callback(userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_visibilitychange_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_fullscreenchange_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_fullscreenchange_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_pointerlockchange_callback(target: *const c_char, userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_pointerlockchange_callback_func>) -> EMSCRIPTEN_RESULT;
+    pub fn emscripten_set_orientationchange_callback(userData: *mut c_void, useCapture: EM_BOOL, callback: Option<em_orientationchange_callback_func>) -> EMSCRIPTEN_RESULT;
 }
+
+#[cfg(not(target_os = "emscripten"))]
+#[path = "html5_fake.rs"]
+mod fake;
+#[cfg(not(target_os = "emscripten"))]
+pub use self::fake::*;
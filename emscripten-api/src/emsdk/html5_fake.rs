@@ -0,0 +1,176 @@
+// Pure rust stand-in for the html5.h functions, used natively.
+// Registered callbacks are kept per thread, tests send events to them with `dispatch`.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_void, c_int, c_char, c_double};
+use super::*;
+
+#[derive(Default)]
+struct Fake {
+    /// (callback, user data) by event type and target
+    callbacks: HashMap<(c_int, String), (usize, usize)>,
+}
+
+thread_local!(static FAKE: RefCell<Fake> = RefCell::new(Fake::default()));
+
+/// name of a target in `dispatch`, special targets are `#document`, `#window` and `#screen`
+unsafe fn to_string(target: *const c_char) -> String {
+    match target as usize {
+        0 => "".to_owned(),
+        EMSCRIPTEN_EVENT_TARGET_DOCUMENT => "#document".to_owned(),
+        EMSCRIPTEN_EVENT_TARGET_WINDOW => "#window".to_owned(),
+        EMSCRIPTEN_EVENT_TARGET_SCREEN => "#screen".to_owned(),
+        _ => CStr::from_ptr(target).to_string_lossy().into_owned(),
+    }
+}
+
+fn set(event_type: c_int, target: String, user_data: *mut c_void, callback: Option<usize>) -> EMSCRIPTEN_RESULT {
+    FAKE.with(|fake| {
+        let mut fake = fake.borrow_mut();
+        match callback {
+            Some(f) => fake.callbacks.insert((event_type, target), (f, user_data as usize)),
+            None => fake.callbacks.remove(&(event_type, target)),
+        };
+    });
+    EMSCRIPTEN_RESULT_SUCCESS
+}
+
+// test controls
+
+/// forget the callbacks of this thread
+pub fn reset() {
+    FAKE.with(|fake| *fake.borrow_mut() = Fake::default());
+}
+
+pub fn is_registered(event_type: c_int, target: &str) -> bool {
+    FAKE.with(|fake| fake.borrow().callbacks.contains_key(&(event_type, target.to_owned())))
+}
+
+/// call the callback registered for `event_type` on `target`, `E` must be the event struct it takes
+pub fn dispatch<E>(event_type: c_int, target: &str, event: &E) -> Option<EM_BOOL> {
+    let cbk = FAKE.with(|fake| fake.borrow().callbacks.get(&(event_type, target.to_owned())).cloned());
+    cbk.map(|(f, user_data)| {
+        let f: extern "C" fn(c_int, *const E, *mut c_void) -> EM_BOOL = unsafe { mem::transmute(f) };
+        f(event_type, event, user_data as *mut c_void)
+    })
+}
+
+// html5.h, there are no elements natively
+
+pub unsafe extern "C" fn emscripten_set_element_css_size(_target: *const c_char, _width: c_double, _height: c_double) -> EMSCRIPTEN_RESULT {
+    EMSCRIPTEN_RESULT_NOT_SUPPORTED
+}
+
+pub unsafe fn emscripten_get_element_css_size(_target: *const c_char, _width: *mut c_double, _height: *mut c_double) -> EMSCRIPTEN_RESULT {
+    EMSCRIPTEN_RESULT_NOT_SUPPORTED
+}
+
+pub unsafe extern "C" fn emscripten_set_keypress_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_KEYPRESS, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_keydown_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_KEYDOWN, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_keyup_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_key_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_KEYUP, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_click_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_CLICK, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mousedown_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSEDOWN, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mouseup_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSEUP, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_dblclick_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_DBLCLICK, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mousemove_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSEMOVE, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mouseenter_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSEENTER, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mouseleave_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSELEAVE, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mouseover_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSEOVER, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_mouseout_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_mouse_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_MOUSEOUT, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_wheel_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_wheel_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_WHEEL, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_resize_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_ui_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_RESIZE, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_scroll_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_ui_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_SCROLL, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_blur_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_BLUR, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_focus_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_FOCUS, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_focusin_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_FOCUSIN, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_focusout_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_focus_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_FOCUSOUT, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_touchstart_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_TOUCHSTART, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_touchend_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_TOUCHEND, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_touchmove_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_TOUCHMOVE, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_touchcancel_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_touch_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_TOUCHCANCEL, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_visibilitychange_callback(userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_visibilitychange_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_VISIBILITYCHANGE, "#document".to_owned(), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_fullscreenchange_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_fullscreenchange_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_FULLSCREENCHANGE, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_pointerlockchange_callback(target: *const c_char, userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_pointerlockchange_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_POINTERLOCKCHANGE, to_string(target), userData, callback.map(|f| f as usize))
+}
+
+pub unsafe extern "C" fn emscripten_set_orientationchange_callback(userData: *mut c_void, _useCapture: EM_BOOL, callback: Option<em_orientationchange_callback_func>) -> EMSCRIPTEN_RESULT {
+    set(EMSCRIPTEN_EVENT_ORIENTATIONCHANGE, "#screen".to_owned(), userData, callback.map(|f| f as usize))
+}
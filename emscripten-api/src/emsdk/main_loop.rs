@@ -0,0 +1,102 @@
+// The browser main loop, `callback` runs each animation frame or `fps` times a second.
+use std::cell::RefCell;
+use super::emscripten;
+use super::Error;
+
+thread_local!(static MAIN_LOOP: RefCell<Option<Box<FnMut()>>> = const { RefCell::new(None) });
+thread_local!(static RUNNING: RefCell<bool> = const { RefCell::new(false) });
+
+/// `fps` 0 follows the display refresh rate, with `simulate_infinite_loop`
+/// this does not return, like a native loop that never ends
+pub fn set_main_loop<F>(callback: F, fps: i32, simulate_infinite_loop: bool) -> Result<(), Error>
+    where F: FnMut() + 'static {
+    if RUNNING.with(|r| *r.borrow()) {
+        return Err(Error::MainLoopRunning);
+    }
+    RUNNING.with(|r| *r.borrow_mut() = true);
+    MAIN_LOOP.with(|l| *l.borrow_mut() = Some(Box::new(callback)));
+    unsafe {
+        emscripten::emscripten_set_main_loop(wrapper, fps, simulate_infinite_loop as i32);
+    }
+    Ok(())
+}
+
+extern "C" fn wrapper() {
+    // taken out while running, the callback may cancel its own loop
+    let callback = MAIN_LOOP.with(|l| l.borrow_mut().take());
+    if let Some(mut callback) = callback {
+        callback();
+        MAIN_LOOP.with(|l| {
+            let mut l = l.borrow_mut();
+            if l.is_none() && is_running() {
+                *l = Some(callback);
+            }
+        });
+    }
+}
+
+/// stop the loop and drop its callback, a new loop can be set after
+pub fn cancel_main_loop() {
+    unsafe {
+        emscripten::emscripten_cancel_main_loop();
+    }
+    RUNNING.with(|r| *r.borrow_mut() = false);
+    MAIN_LOOP.with(|l| l.borrow_mut().take());
+}
+
+pub fn pause_main_loop() {
+    unsafe {
+        emscripten::emscripten_pause_main_loop();
+    }
+}
+
+pub fn resume_main_loop() {
+    unsafe {
+        emscripten::emscripten_resume_main_loop();
+    }
+}
+
+pub fn is_running() -> bool {
+    RUNNING.with(|r| *r.borrow())
+}
+
+/// keep the runtime alive after main returns, so callbacks can still run
+pub fn exit_with_live_runtime() {
+    unsafe {
+        emscripten::emscripten_exit_with_live_runtime();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    #[test]
+    fn run_pause_and_cancel() {
+        emscripten::reset();
+        let frames = Rc::new(Cell::new(0));
+        let f = frames.clone();
+        set_main_loop(move || {
+            f.set(f.get() + 1);
+            if f.get() == 3 {
+                cancel_main_loop();
+            }
+        }, 30, true).unwrap();
+        assert_eq!(emscripten::main_loop_fps(), Some(30));
+        assert_eq!(set_main_loop(|| (), 0, false), Err(Error::MainLoopRunning));
+
+        assert!(emscripten::run_frame());
+        pause_main_loop();
+        assert!(!emscripten::run_frame());
+        resume_main_loop();
+        assert!(emscripten::run_frame());
+        // cancelled from inside the third frame
+        assert!(emscripten::run_frame());
+        assert!(!emscripten::run_frame());
+        assert_eq!(frames.get(), 3);
+        assert!(!is_running());
+        assert!(set_main_loop(|| (), 0, false).is_ok());
+    }
+}
@@ -1,2 +1,56 @@
-pub mod html5;
+// Bindings for the emscripten C API.
+// `emscripten` and `html5` are the raw functions, the other modules wrap them
+// with rust strings, closures and `Result`. Natively the raw functions come
+// from `fake.rs`, a pure rust stand-in, so the wrappers can be unit tested.
+use std::ffi::CString;
+use std::fmt;
+
+#[cfg(target_os = "emscripten")]
+pub mod emscripten;
+#[cfg(not(target_os = "emscripten"))]
+#[path = "fake.rs"]
 pub mod emscripten;
+pub mod html5;
+pub mod events;
+pub mod main_loop;
+pub mod wget;
+pub mod preload;
+pub mod time;
+pub mod script;
+pub mod store;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// the string has a nul byte, so it cannot be passed to C
+    Nul(String),
+    /// download failed, with the http status if known
+    Fetch { url: String, status: Option<i32> },
+    /// no preload plugin handles the file, or it failed to decode
+    Preload(String),
+    /// only one main loop can run at once
+    MainLoopRunning,
+    Script(String),
+    /// an html5.h call failed
+    Html5 { event: String, reason: &'static str },
+    /// persistent store io or (de)serialization failed
+    Store(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Nul(ref s) => write!(f, "string has a nul byte: {:?}", s),
+            Error::Fetch { ref url, status: Some(status) } => write!(f, "cannot fetch {}: status {}", url, status),
+            Error::Fetch { ref url, status: None } => write!(f, "cannot fetch {}", url),
+            Error::Preload(ref s) => write!(f, "cannot preload {}", s),
+            Error::MainLoopRunning => write!(f, "a main loop is running already"),
+            Error::Script(ref s) => write!(f, "script failed: {}", s),
+            Error::Html5 { ref event, reason } => write!(f, "{}: {}", event, reason),
+            Error::Store(ref s) => write!(f, "store: {}", s),
+        }
+    }
+}
+
+fn c_string(s: &str) -> Result<CString, Error> {
+    CString::new(s).map_err(|_| Error::Nul(s.to_owned()))
+}
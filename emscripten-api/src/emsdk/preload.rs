@@ -0,0 +1,112 @@
+// Decode files with the browser, so SDL_image and friends can load them.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::{c_void, c_char, c_int};
+use super::emscripten;
+use super::{c_string, Error};
+
+type Callback = Box<FnOnce(Result<String, Error>)>;
+
+// the file callbacks only get the file name, find the closure by it
+thread_local!(static PENDING: RefCell<HashMap<String, Vec<Callback>>> = RefCell::new(HashMap::new()));
+
+/// preload `file` from the emscripten FS, the callback gets its name
+pub fn run_preload_plugins<F>(file: &str, cbk: F) -> Result<(), Error>
+    where F: FnOnce(Result<String, Error>) + 'static {
+    let c_file = c_string(file)?;
+    PENDING.with(|p| p.borrow_mut().entry(file.to_owned()).or_insert(vec![]).push(Box::new(cbk)));
+    let ret = unsafe { emscripten::emscripten_run_preload_plugins(c_file.as_ptr(), on_file_load, on_file_error) };
+    if ret != 0 {
+        PENDING.with(|p| {
+            let mut p = p.borrow_mut();
+            let empty = p.get_mut(file).map(|cbks| { cbks.pop(); cbks.is_empty() }).unwrap_or(false);
+            if empty {
+                p.remove(file);
+            }
+        });
+        return Err(Error::Preload(format!("{}: no plugin for this file type", file)));
+    }
+    Ok(())
+}
+
+fn finish(file: *const c_char, result: &Fn(String) -> Result<String, Error>) {
+    let file = unsafe { CStr::from_ptr(file) }.to_string_lossy().into_owned();
+    let cbk = PENDING.with(|p| {
+        let mut p = p.borrow_mut();
+        let cbk = p.get_mut(&file).and_then(|cbks| if cbks.is_empty() { None } else { Some(cbks.remove(0)) });
+        if p.get(&file).map(|cbks| cbks.is_empty()).unwrap_or(false) {
+            p.remove(&file);
+        }
+        cbk
+    });
+    if let Some(cbk) = cbk {
+        cbk(result(file));
+    }
+}
+
+extern "C" fn on_file_load(file: *const c_char) {
+    finish(file, &|file| Ok(file));
+}
+
+extern "C" fn on_file_error(file: *const c_char) {
+    finish(file, &|file| Err(Error::Preload(file)));
+}
+
+/// decode `data` as a file with extension `suffix`,
+/// the callback gets the name of the file it is saved to
+pub fn run_preload_plugins_data<F>(data: &[u8], suffix: &str, cbk: F) -> Result<(), Error>
+    where F: FnOnce(Result<String, Error>) + 'static {
+    let c_suffix = c_string(suffix)?;
+    let arg: Box<(String, Callback)> = Box::new((suffix.to_owned(), Box::new(cbk)));
+    unsafe {
+        // the data is copied before this returns
+        emscripten::emscripten_run_preload_plugins_data(data.as_ptr() as *const c_char, data.len() as c_int,
+                                                        c_suffix.as_ptr(), Box::into_raw(arg) as *mut c_void,
+                                                        on_data_load, on_data_error);
+    }
+    Ok(())
+}
+
+extern "C" fn on_data_load(arg: *mut c_void, file: *const c_char) {
+    let arg = unsafe { Box::from_raw(arg as *mut (String, Callback)) };
+    let file = unsafe { CStr::from_ptr(file) }.to_string_lossy().into_owned();
+    (arg.1)(Ok(file));
+}
+
+extern "C" fn on_data_error(arg: *mut c_void) {
+    let arg = unsafe { Box::from_raw(arg as *mut (String, Callback)) };
+    (arg.1)(Err(Error::Preload(format!("data with suffix {}", arg.0))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn preload_files_and_data() {
+        emscripten::reset();
+        emscripten::write_file("icon.png", b"png");
+        let results = Rc::new(RefCell::new(vec![]));
+        let (r1, r2, r3, r4) = (results.clone(), results.clone(), results.clone(), results.clone());
+
+        run_preload_plugins("icon.png", move |r| r1.borrow_mut().push(r)).unwrap();
+        run_preload_plugins("gone.jpg", move |r| r2.borrow_mut().push(r)).unwrap();
+        run_preload_plugins_data(b"png", "png", move |r| r3.borrow_mut().push(r)).unwrap();
+        run_preload_plugins_data(b"txt", "txt", move |r| r4.borrow_mut().push(r)).unwrap();
+        match run_preload_plugins("notes.txt", |_| ()) {
+            Err(Error::Preload(_)) => (),
+            r => panic!("expect preload error, got {:?}", r),
+        }
+
+        emscripten::advance(0.);
+        let results = results.borrow();
+        assert_eq!(results[0], Ok("icon.png".to_owned()));
+        assert_eq!(results[1], Err(Error::Preload("gone.jpg".to_owned())));
+        let name = results[2].clone().unwrap();
+        assert_eq!(emscripten::read_file(&name), Some(b"png".to_vec()));
+        assert_eq!(results[3], Err(Error::Preload("data with suffix txt".to_owned())));
+        PENDING.with(|p| assert!(p.borrow().is_empty()));
+    }
+}
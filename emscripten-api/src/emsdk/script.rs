@@ -0,0 +1,53 @@
+// Run javascript with `eval`, the results are converted to rust types.
+use std::ffi::CStr;
+use super::emscripten;
+use super::{c_string, Error};
+
+pub fn run_script(script: &str) -> Result<(), Error> {
+    let s = c_string(script)?;
+    unsafe {
+        emscripten::emscripten_run_script(s.as_ptr());
+    }
+    Ok(())
+}
+
+/// result of `script` converted to an int
+pub fn run_script_int(script: &str) -> Result<i32, Error> {
+    let s = c_string(script)?;
+    Ok(unsafe { emscripten::emscripten_run_script_int(s.as_ptr()) })
+}
+
+/// result of `script` converted to a string
+pub fn run_script_string(script: &str) -> Result<String, Error> {
+    let s = c_string(script)?;
+    unsafe {
+        // owned by emscripten, copy it before the next call
+        let ret = emscripten::emscripten_run_script_string(s.as_ptr());
+        if ret.is_null() {
+            return Err(Error::Script(format!("no result from `{}`", script)));
+        }
+        Ok(CStr::from_ptr(ret).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_and_bad_strings() {
+        emscripten::reset();
+        emscripten::set_script_result("20 * 10", "200");
+        emscripten::set_script_result("navigator.language", "en-US");
+
+        assert_eq!(run_script_int("20 * 10"), Ok(200));
+        assert_eq!(run_script_string("navigator.language"), Ok("en-US".to_owned()));
+        assert!(run_script("console.log(1)").is_ok());
+        match run_script_string("undefined_thing") {
+            Err(Error::Script(_)) => (),
+            r => panic!("expect script error, got {:?}", r),
+        }
+        assert_eq!(run_script("a\0b"), Err(Error::Nul("a\0b".to_owned())));
+        assert_eq!(emscripten::scripts(), vec!["20 * 10", "navigator.language", "console.log(1)", "undefined_thing"]);
+    }
+}
@@ -0,0 +1,285 @@
+// Key/value store that survives page reloads.
+// On the web a directory is mounted on IDBFS, values live in a json file
+// there and `load`/`flush` sync it with IndexedDB. Natively the directory is
+// a plain one on disk and both call back right away.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+use super::Error;
+
+const STORE_FILE: &str = "store.json";
+
+struct StoreInner {
+    dir: PathBuf,
+    values: BTreeMap<String, Value>,
+}
+
+/// cheap handle, clones share the values
+#[derive(Clone)]
+pub struct PersistentStore(Rc<RefCell<StoreInner>>);
+
+fn io_error(path: &Path, e: ::std::io::Error) -> Error {
+    Error::Store(format!("{}: {}", path.display(), e))
+}
+
+impl PersistentStore {
+    /// mount `dir`, it is empty until `load`
+    pub fn mount(dir: &str) -> Result<PersistentStore, Error> {
+        sync::mount(dir)?;
+        Ok(PersistentStore(Rc::new(RefCell::new(StoreInner {
+            dir: PathBuf::from(dir),
+            values: BTreeMap::new(),
+        }))))
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.0.borrow().dir.clone()
+    }
+
+    /// read the values saved by the last `flush`, unflushed changes are lost
+    pub fn load<F>(&self, cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        let store = self.clone();
+        sync::sync_from_storage(move |res| {
+            cbk(res.and_then(|_| store.read_file()));
+        });
+    }
+
+    /// save the values, the callback runs once they are stored
+    pub fn flush<F>(&self, cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        if let Err(e) = self.write_file() {
+            cbk(Err(e));
+            return;
+        }
+        sync::sync_to_storage(cbk);
+    }
+
+    /// `None` if there is no such key, `Err` if the value is not a `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        match self.0.borrow().values.get(key) {
+            Some(v) => serde_json::from_value(v.clone())
+                .map(Some)
+                .map_err(|e| Error::Store(format!("{}: {}", key, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// kept in memory until `flush`
+    pub fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<(), Error> {
+        let v = serde_json::to_value(value).map_err(|e| Error::Store(format!("{}: {}", key, e)))?;
+        self.0.borrow_mut().values.insert(key.to_owned(), v);
+        Ok(())
+    }
+
+    /// true if there was a value
+    pub fn remove(&self, key: &str) -> bool {
+        self.0.borrow_mut().values.remove(key).is_some()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.0.borrow().values.keys().cloned().collect()
+    }
+
+    fn read_file(&self) -> Result<(), Error> {
+        let path = self.dir().join(STORE_FILE);
+        let mut s = String::new();
+        match File::open(&path) {
+            Ok(mut f) => {
+                f.read_to_string(&mut s).map_err(|e| io_error(&path, e))?;
+            },
+            // nothing saved yet
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(io_error(&path, e)),
+        }
+        let values = if s.is_empty() {
+            BTreeMap::new()
+        } else {
+            serde_json::from_str(&s).map_err(|e| Error::Store(format!("{}: {}", path.display(), e)))?
+        };
+        self.0.borrow_mut().values = values;
+        Ok(())
+    }
+
+    fn write_file(&self) -> Result<(), Error> {
+        let path = self.dir().join(STORE_FILE);
+        let s = serde_json::to_string(&self.0.borrow().values).map_err(|e| Error::Store(e.to_string()))?;
+        let mut f = File::create(&path).map_err(|e| io_error(&path, e))?;
+        f.write_all(s.as_bytes()).map_err(|e| io_error(&path, e))
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+mod sync {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::os::raw::c_int;
+    use serde_json;
+    use super::super::script;
+    use super::super::Error;
+
+    type Callback = Box<FnOnce(Result<(), Error>)>;
+
+    thread_local!(static MOUNTED: RefCell<HashSet<String>> = RefCell::new(HashSet::new()));
+    thread_local!(static PENDING: RefCell<(c_int, HashMap<c_int, Callback>)> = RefCell::new((0, HashMap::new())));
+
+    /// as a javascript string literal
+    fn js_str(s: &str) -> String {
+        serde_json::to_string(s).unwrap()
+    }
+
+    pub fn mount(dir: &str) -> Result<(), Error> {
+        if MOUNTED.with(|m| m.borrow().contains(dir)) {
+            return Ok(());
+        }
+        let ok = script::run_script_int(&format!("(function (dir) {{\
+            try {{ FS.mkdir(dir); }} catch (e) {{}}\
+            try {{ FS.mount(IDBFS, {{}}, dir); return 1; }} catch (e) {{ return 0; }}\
+        }})({})", js_str(dir)))?;
+        if ok != 1 {
+            return Err(Error::Store(format!("cannot mount IDBFS on {}", dir)));
+        }
+        MOUNTED.with(|m| m.borrow_mut().insert(dir.to_owned()));
+        Ok(())
+    }
+
+    /// `populate` copies IndexedDB to the FS, otherwise the FS to IndexedDB
+    fn syncfs<F>(populate: bool, cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        let id = PENDING.with(|p| {
+            let mut p = p.borrow_mut();
+            p.0 += 1;
+            let id = p.0;
+            p.1.insert(id, Box::new(cbk));
+            id
+        });
+        let res = script::run_script(&format!("FS.syncfs({}, function (err) {{\
+            Module.ccall('emsdk_store_synced', null, ['number', 'number'], [{}, err ? 1 : 0]);\
+        }})", populate, id));
+        if let Err(e) = res {
+            synced(id, Err(e));
+        }
+    }
+
+    pub fn sync_from_storage<F>(cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        syncfs(true, cbk);
+    }
+
+    pub fn sync_to_storage<F>(cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        syncfs(false, cbk);
+    }
+
+    fn synced(id: c_int, res: Result<(), Error>) {
+        let cbk = PENDING.with(|p| p.borrow_mut().1.remove(&id));
+        if let Some(cbk) = cbk {
+            cbk(res);
+        }
+    }
+
+    /// called by the syncfs callback
+    #[no_mangle]
+    pub extern "C" fn emsdk_store_synced(id: c_int, failed: c_int) {
+        let res = if failed != 0 { Err(Error::Store("IDBFS sync failed".to_owned())) } else { Ok(()) };
+        synced(id, res);
+    }
+}
+
+#[cfg(not(target_os = "emscripten"))]
+mod sync {
+    use std::fs;
+    use super::super::Error;
+
+    pub fn mount(dir: &str) -> Result<(), Error> {
+        fs::create_dir_all(dir).map_err(|e| Error::Store(format!("{}: {}", dir, e)))
+    }
+
+    // files are written straight to disk
+
+    pub fn sync_from_storage<F>(cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        cbk(Ok(()));
+    }
+
+    pub fn sync_to_storage<F>(cbk: F)
+        where F: FnOnce(Result<(), Error>) + 'static {
+        cbk(Ok(()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        volume: u8,
+        name: String,
+    }
+
+    fn temp_dir(name: &str) -> String {
+        env::temp_dir().join(format!("emsdk-store-{}-{}", name, ::std::process::id()))
+            .to_string_lossy().into_owned()
+    }
+
+    fn remove_dir(dir: &str) {
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn flush_and_load_again() {
+        let dir = temp_dir("flush");
+        remove_dir(&dir);
+        let store = PersistentStore::mount(&dir).unwrap();
+        let done = Rc::new(RefCell::new(vec![]));
+
+        let d = done.clone();
+        store.load(move |res| d.borrow_mut().push(res));
+        assert!(store.keys().is_empty());
+
+        let settings = Settings { volume: 7, name: "amadeus".to_owned() };
+        store.set("settings", &settings).unwrap();
+        store.set("visits", &3).unwrap();
+        let d = done.clone();
+        store.flush(move |res| d.borrow_mut().push(res));
+
+        let again = PersistentStore::mount(&dir).unwrap();
+        let d = done.clone();
+        again.load(move |res| d.borrow_mut().push(res));
+        assert_eq!(*done.borrow(), vec![Ok(()), Ok(()), Ok(())]);
+        assert_eq!(again.keys(), vec!["settings", "visits"]);
+        assert_eq!(again.get("settings"), Ok(Some(settings)));
+        assert_eq!(again.get::<u32>("visits"), Ok(Some(3)));
+        assert_eq!(again.get::<u32>("missing"), Ok(None));
+        assert!(again.get::<Settings>("visits").is_err());
+
+        assert!(again.remove("visits"));
+        assert!(!again.remove("visits"));
+        remove_dir(&dir);
+    }
+
+    #[test]
+    fn broken_file() {
+        let dir = temp_dir("broken");
+        remove_dir(&dir);
+        let store = PersistentStore::mount(&dir).unwrap();
+        File::create(Path::new(&dir).join(STORE_FILE)).unwrap().write_all(b"{ nope").unwrap();
+        let res = Rc::new(RefCell::new(None));
+        let r = res.clone();
+        store.load(move |e| *r.borrow_mut() = Some(e));
+        match *res.borrow() {
+            Some(Err(Error::Store(_))) => (),
+            ref r => panic!("expect store error, got {:?}", r),
+        }
+        remove_dir(&dir);
+    }
+}
@@ -0,0 +1,44 @@
+// Clock and timers of the browser.
+use std::os::raw::c_void;
+use super::emscripten;
+
+/// milliseconds since the page loaded, with sub-millisecond precision
+pub fn now() -> f64 {
+    unsafe { emscripten::emscripten_get_now() }
+}
+
+/// run `f` once after `ms` milliseconds, without blocking
+pub fn set_timeout<F>(ms: u32, f: F)
+    where F: FnOnce() + 'static {
+    let arg: Box<Box<FnOnce()>> = Box::new(Box::new(f));
+    unsafe {
+        emscripten::emscripten_async_call(on_timeout, Box::into_raw(arg) as *mut c_void, ms as i32);
+    }
+}
+
+extern "C" fn on_timeout(arg: *mut c_void) {
+    let f = unsafe { Box::from_raw(arg as *mut Box<FnOnce()>) };
+    f();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    #[test]
+    fn timeouts_run_in_order() {
+        emscripten::reset();
+        let log = Rc::new(RefCell::new(vec![]));
+        let (l1, l2) = (log.clone(), log.clone());
+        set_timeout(200, move || l1.borrow_mut().push(now()));
+        set_timeout(50, move || l2.borrow_mut().push(now()));
+
+        emscripten::advance(100.);
+        assert_eq!(*log.borrow(), vec![50.]);
+        emscripten::advance(100.);
+        assert_eq!(*log.borrow(), vec![50., 200.]);
+        assert_eq!(now(), 200.);
+    }
+}
@@ -0,0 +1,119 @@
+// Download files without blocking, the callback gets the result once done.
+use std::ffi::CStr;
+use std::os::raw::{c_void, c_int, c_uint, c_char};
+use std::slice;
+use super::emscripten;
+use super::{c_string, Error};
+
+struct Request<T> {
+    url: String,
+    cbk: Box<FnOnce(Result<T, Error>)>,
+}
+
+impl<T> Request<T> {
+    fn into_raw<F>(url: &str, cbk: F) -> *mut c_void
+        where F: FnOnce(Result<T, Error>) + 'static {
+        Box::into_raw(Box::new(Request { url: url.to_owned(), cbk: Box::new(cbk) })) as *mut c_void
+    }
+    /// only one of the onload and onerror callbacks runs, it takes the request back
+    unsafe fn from_raw(arg: *mut c_void) -> Box<Request<T>> {
+        Box::from_raw(arg as *mut Request<T>)
+    }
+}
+
+/// save `url` to `file` in the emscripten FS, the callback gets the file path
+pub fn async_wget<F>(url: &str, file: &str, cbk: F) -> Result<(), Error>
+    where F: FnOnce(Result<String, Error>) + 'static {
+    let c_url = c_string(url)?;
+    let c_file = c_string(file)?;
+    let method = c_string("GET")?;
+    let param = c_string("")?;
+    unsafe {
+        emscripten::emscripten_async_wget2(c_url.as_ptr(), c_file.as_ptr(), method.as_ptr(), param.as_ptr(),
+                                           Request::into_raw(url, cbk),
+                                           on_file_load, on_file_error, None);
+    }
+    Ok(())
+}
+
+extern "C" fn on_file_load(_handle: c_uint, arg: *mut c_void, file: *const c_char) {
+    unsafe {
+        let req = Request::<String>::from_raw(arg);
+        let file = CStr::from_ptr(file).to_string_lossy().into_owned();
+        (req.cbk)(Ok(file));
+    }
+}
+
+extern "C" fn on_file_error(_handle: c_uint, arg: *mut c_void, status: c_int) {
+    unsafe {
+        let req = Request::<String>::from_raw(arg);
+        (req.cbk)(Err(Error::Fetch { url: req.url.clone(), status: Some(status) }));
+    }
+}
+
+/// download `url` into memory
+pub fn async_wget_data<F>(url: &str, cbk: F) -> Result<(), Error>
+    where F: FnOnce(Result<Vec<u8>, Error>) + 'static {
+    let c_url = c_string(url)?;
+    unsafe {
+        emscripten::emscripten_async_wget_data(c_url.as_ptr(), Request::into_raw(url, cbk), on_data_load, on_data_error);
+    }
+    Ok(())
+}
+
+extern "C" fn on_data_load(arg: *mut c_void, buf: *mut c_void, size: c_int) {
+    unsafe {
+        let req = Request::<Vec<u8>>::from_raw(arg);
+        // freed by emscripten after this returns
+        let data = slice::from_raw_parts(buf as *const u8, size as usize).to_vec();
+        (req.cbk)(Ok(data));
+    }
+}
+
+extern "C" fn on_data_error(arg: *mut c_void) {
+    unsafe {
+        let req = Request::<Vec<u8>>::from_raw(arg);
+        (req.cbk)(Err(Error::Fetch { url: req.url.clone(), status: None }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    #[test]
+    fn wget_file_and_data() {
+        emscripten::reset();
+        emscripten::serve("icon.png", b"png");
+        let results = Rc::new(RefCell::new(vec![]));
+        let (r1, r2, r3) = (results.clone(), results.clone(), results.clone());
+
+        async_wget("icon.png", "/tmp/icon.png", move |r| r1.borrow_mut().push(format!("{:?}", r))).unwrap();
+        async_wget_data("icon.png", move |r| r2.borrow_mut().push(format!("{:?}", r))).unwrap();
+        async_wget_data("missing.png", move |r| r3.borrow_mut().push(format!("{:?}", r))).unwrap();
+        // nothing happens until the browser gets back
+        assert!(results.borrow().is_empty());
+
+        emscripten::advance(0.);
+        assert_eq!(*results.borrow(), vec![
+            "Ok(\"/tmp/icon.png\")".to_owned(),
+            "Ok([112, 110, 103])".to_owned(),
+            "Err(Fetch { url: \"missing.png\", status: None })".to_owned(),
+        ]);
+        assert_eq!(emscripten::read_file("/tmp/icon.png"), Some(b"png".to_vec()));
+    }
+
+    #[test]
+    fn wget_error_status() {
+        emscripten::reset();
+        let result = Rc::new(RefCell::new(None));
+        let r = result.clone();
+        async_wget("nope.txt", "nope.txt", move |res| *r.borrow_mut() = Some(res)).unwrap();
+        emscripten::advance(0.);
+        assert_eq!(*result.borrow(), Some(Err(Error::Fetch { url: "nope.txt".to_owned(), status: Some(404) })));
+
+        assert_eq!(async_wget_data("a\0", |_| ()), Err(Error::Nul("a\0".to_owned())));
+    }
+}
@@ -1,35 +1,34 @@
+extern crate serde;
+extern crate serde_json;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
+
+// the bindings are a library, this demo only uses part of them
+#[allow(dead_code)]
 mod emsdk;
 
-use std::os::raw::{c_char, c_int, c_void};
 use std::ffi::CString;
-use std::fs::File;
-use std::io::prelude::*;
-use emsdk::{emscripten, html5};
+use emsdk::{events, html5, main_loop, script, wget};
+use emsdk::events::{EventType, Target};
+use emsdk::store::PersistentStore;
 
 fn main() {
     {
-        println!("test emscripten_asm_const");
-
-        unsafe {
-            let s = concat!("console.log(123)", "\0");
-            emscripten::emscripten_asm_const(s as *const _ as *const c_char);
+        println!("test emscripten_run_script");
 
-            let s1 = concat!("return $0 * 10;", "\0");
-            let ret = emscripten::emscripten_asm_const_int(s1 as *const _ as *const c_char, 20_i32);
-            println!("ret: {}", ret);
-        }
+        script::run_script("console.log(123)").unwrap();
+        let ret = script::run_script_int("20 * 10").unwrap();
+        println!("ret: {}", ret);
     }
 
     {
         println!("test emscripten_async_wget");
 
-        unsafe {
-            let url = concat!("icon.png", "\0");
-            let file = concat!("icon.png", "\0");
-            emscripten::emscripten_async_wget(url as *const _ as *const c_char,
-                                              file as *const _ as *const c_char,
-                                              onload, onerror);
-        }
+        wget::async_wget("icon.png", "icon.png", |res| match res {
+            Ok(file) => println!("file {} loaded", file),
+            Err(e) => println!("file load error: {}", e),
+        }).unwrap();
     }
 
     {
@@ -45,56 +44,47 @@ fn main() {
     }
 
     {
-        let s = concat!("\
-FS.mkdir('/persist');\
-FS.mount(IDBFS, {}, '/persist');\
-FS.syncfs(true, function (err) {\
-    assert(!err);\
-    ccall('test');\
-});\
-", "\0");
-        unsafe {
-            emscripten::emscripten_asm_const(s as *const _ as *const c_char);
-            emscripten::emscripten_exit_with_live_runtime();
-        }
-    }
-}
+        println!("test html5 event callbacks");
 
-#[no_mangle]
-pub extern "C" fn onload(file: *const c_char) {
-    unsafe {
-        let file = CString::from_raw(file as *mut c_char).into_string().unwrap();
-        println!("file {} loaded", file);
+        // keep the canvas as large as the page instead of measuring it once
+        events::set_ui_callback(EventType::Resize, Target::Window, false, |e| {
+            let size = (e.window_inner_width as f64, e.window_inner_height as f64);
+            let dom = CString::new("canvas").unwrap();
+            unsafe {
+                html5::emscripten_set_element_css_size(dom.as_ptr(), size.0, size.1);
+            }
+            println!("resized: {:?}, canvas: {:?}", size, events::get_element_css_size("canvas"));
+            false
+        }).unwrap();
+        events::set_visibilitychange_callback(false, |e| {
+            println!("page hidden: {}, {:?}", e.hidden, e.visibility_state);
+            false
+        }).unwrap();
+        events::set_key_callback(EventType::KeyDown, Target::Window, false, |e| {
+            println!("key down: {} {:?}", e.key, e.modifiers);
+            false
+        }).unwrap();
     }
-}
-
-#[no_mangle]
-pub extern "C" fn onerror(file: *const c_char) {
-    unsafe {
-        let file = CString::from_raw(file as *mut c_char).into_string().unwrap();
-        println!("file {} load error", file);
-    }
-}
-
-#[no_mangle]
-pub extern "C" fn test() {
-    let file_name = "/persist/foo.txt";
 
-    if let Ok(mut file) = File::open(file_name) {
-        let mut txt = String::new();
-        file.read_to_string(&mut txt).unwrap();
-        println!("File content: {}", txt);
-    } else {
-        let mut mf = File::create(file_name).unwrap();
-        mf.write_all("Hello, emscripten!".as_bytes());
-        println!("Write file {}", file_name);
+    {
+        println!("test persistent store");
 
-        let s = concat!("FS.syncfs(function(err) {\
-assert(!err);\
-})", "\0");
-        unsafe {
-            emscripten::emscripten_asm_const(s as *const _ as *const c_char);
-        }
+        let store = PersistentStore::mount("/persist").unwrap();
+        let s = store.clone();
+        store.load(move |res| {
+            if let Err(e) = res {
+                println!("load failed: {}", e);
+                return;
+            }
+            match s.get::<String>("greeting") {
+                Ok(Some(txt)) => println!("Stored greeting: {}", txt),
+                _ => {
+                    s.set("greeting", &"Hello, emscripten!").unwrap();
+                    s.flush(|res| println!("Saved greeting: {:?}", res));
+                },
+            }
+        });
+        // keep the runtime for the async callbacks
+        main_loop::exit_with_live_runtime();
     }
-
 }
@@ -0,0 +1,14 @@
+// taken from https://github.com/Gigoteur/PX8/blob/master/src/px8/emscripten.rs
+#![allow(non_camel_case_types)]
+
+use std::os::raw::{c_int, c_char};
+
+pub type em_callback_func = extern "C" fn();
+
+extern "C" {
+    pub fn emscripten_set_main_loop(func: em_callback_func,
+                                    fps: c_int,
+                                    simulate_infinite_loop: c_int);
+    pub fn emscripten_cancel_main_loop();
+    pub fn emscripten_run_script_int(script: *const c_char) -> c_int;
+}
@@ -0,0 +1,217 @@
+// Main loop shared by the sdl2 demos.
+// On the web the browser drives the loop through `emscripten_set_main_loop`,
+// natively frames are paced with a sleep. The loop owns its closure, so it
+// can run after the function that started it has returned.
+#[cfg(target_os = "emscripten")]
+mod emscripten;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(target_os = "emscripten")]
+use std::cell::RefCell;
+#[cfg(target_os = "emscripten")]
+use std::ffi::CString;
+#[cfg(not(target_os = "emscripten"))]
+use std::thread::sleep;
+#[cfg(not(target_os = "emscripten"))]
+use std::time::{Duration, Instant};
+
+/// returned by the loop callback each frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoopControl {
+    Continue,
+    Exit,
+}
+
+/// pause, resume or stop a running loop, from the callback or anywhere else
+#[derive(Clone, Default)]
+pub struct LoopHandle {
+    paused: Arc<AtomicBool>,
+    exit: Arc<AtomicBool>,
+}
+
+impl LoopHandle {
+    /// the callback is not called until `resume`
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+    /// stop after the current frame, works while paused too
+    pub fn exit(&self) {
+        self.exit.store(true, Ordering::SeqCst);
+    }
+    fn should_exit(&self) -> bool {
+        self.exit.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Default)]
+pub struct MainLoop {
+    fps: Option<u32>,
+    handle: LoopHandle,
+}
+
+/// paced at 60fps natively, by the browser refresh rate on the web
+pub fn run_main_loop<F>(callback: F)
+    where F: FnMut() -> LoopControl + 'static {
+    MainLoop::new().run(callback);
+}
+
+impl MainLoop {
+    pub fn new() -> MainLoop {
+        MainLoop::default()
+    }
+    /// frames per second to aim for
+    pub fn set_fps(&mut self, fps: u32) {
+        self.fps = if fps > 0 { Some(fps) } else { None };
+    }
+    pub fn handle(&self) -> LoopHandle {
+        self.handle.clone()
+    }
+
+    /// call `callback` each frame until it returns `Exit` or the loop is told to exit,
+    /// on the web this never returns, the browser keeps calling after main ends
+    #[cfg(target_os = "emscripten")]
+    pub fn run<F>(self, mut callback: F)
+        where F: FnMut() -> LoopControl + 'static {
+        let handle = self.handle;
+        MAIN_LOOP_CALLBACK.with(|cbk| {
+            *cbk.borrow_mut() = Some(Box::new(move || {
+                if handle.should_exit() {
+                    return LoopControl::Exit;
+                }
+                if handle.is_paused() {
+                    return LoopControl::Continue;
+                }
+                callback()
+            }));
+        });
+
+        unsafe {
+            // there can only be one main loop, replace the running one
+            emscripten::emscripten_cancel_main_loop();
+            // 0 fps means requestAnimationFrame
+            emscripten::emscripten_set_main_loop(wrapper, self.fps.unwrap_or(0) as i32, 1);
+        }
+
+        extern "C" fn wrapper() {
+            // take the callback out, so it may start another loop
+            let cbk = MAIN_LOOP_CALLBACK.with(|cbk| cbk.borrow_mut().take());
+            if let Some(mut callback) = cbk {
+                match callback() {
+                    LoopControl::Continue => MAIN_LOOP_CALLBACK.with(|cbk| {
+                        let mut cbk = cbk.borrow_mut();
+                        if cbk.is_none() {
+                            *cbk = Some(callback);
+                        }
+                    }),
+                    LoopControl::Exit => unsafe {
+                        emscripten::emscripten_cancel_main_loop();
+                    },
+                }
+            }
+        }
+    }
+
+    /// call `callback` each frame until it returns `Exit` or the loop is told to exit
+    #[cfg(not(target_os = "emscripten"))]
+    pub fn run<F>(self, mut callback: F)
+        where F: FnMut() -> LoopControl {
+        let frame_time = Duration::new(0, 1_000_000_000 / self.fps.unwrap_or(60));
+        while !self.handle.should_exit() {
+            let frame_start = Instant::now();
+
+            if !self.handle.is_paused() && callback() == LoopControl::Exit {
+                break;
+            }
+
+            let draw_time = Instant::now().duration_since(frame_start);
+            if frame_time > draw_time {
+                // framerate control
+                sleep(frame_time - draw_time);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+thread_local!(static MAIN_LOOP_CALLBACK: RefCell<Option<Box<FnMut() -> LoopControl>>> = RefCell::new(None));
+
+#[cfg(target_os = "emscripten")]
+fn run_script_int(script: &str) -> i32 {
+    let script = CString::new(script).unwrap();
+    unsafe { emscripten::emscripten_run_script_int(script.as_ptr()) }
+}
+
+/// size of the page body
+#[cfg(target_os = "emscripten")]
+pub fn get_window_dimention() -> (u32, u32) {
+    (run_script_int("document.body.clientWidth") as u32,
+     run_script_int("document.body.clientHeight") as u32)
+}
+
+/// size of the canvas sdl renders to
+#[cfg(target_os = "emscripten")]
+pub fn get_canvas_dimention() -> (u32, u32) {
+    (run_script_int("Module.canvas.clientWidth") as u32,
+     run_script_int("Module.canvas.clientHeight") as u32)
+}
+
+#[cfg(not(target_os = "emscripten"))]
+pub fn get_window_dimention() -> (u32, u32) {
+    (640, 500)
+}
+
+#[cfg(not(target_os = "emscripten"))]
+pub fn get_canvas_dimention() -> (u32, u32) {
+    get_window_dimention()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn exit_from_callback() {
+        let mut n = 0;
+        let mut l = MainLoop::new();
+        l.set_fps(1000);
+        l.run(|| {
+            n += 1;
+            if n == 3 { LoopControl::Exit } else { LoopControl::Continue }
+        });
+        assert_eq!(n, 3);
+    }
+
+    #[test]
+    fn pause_resume_and_exit() {
+        let mut l = MainLoop::new();
+        l.set_fps(1000);
+        let handle = l.handle();
+        let remote = l.handle();
+        let mut n = 0;
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            assert!(remote.is_paused());
+            remote.resume();
+        });
+        l.run(|| {
+            n += 1;
+            match n {
+                // not called again until resumed by the thread
+                1 => handle.pause(),
+                2 => handle.exit(),
+                _ => panic!("called after exit"),
+            }
+            LoopControl::Continue
+        });
+        t.join().unwrap();
+        assert_eq!(n, 2);
+    }
+}
@@ -1,23 +1,16 @@
 extern crate sdl2;
-#[cfg(target_os = "emscripten")]
-#[macro_use]
-extern crate stdweb;
-
-use std::process;
-use std::thread::sleep;
-use std::time::{Instant, Duration};
+extern crate main_loop;
 
 use sdl2::pixels::Color;
 use sdl2::event::Event;
 use sdl2::mouse::MouseButton;
 use sdl2::rect::{Rect, Point};
 use sdl2::keyboard::Keycode;
+use main_loop::LoopControl;
 
 #[cfg(not(target_os = "emscripten"))]
 use sdl2::gfx::primitives::DrawRenderer;
 
-#[cfg(target_os = "emscripten")]
-mod emscripten;
 mod frame_rate;
 mod box_shape;
 mod utils;
@@ -25,12 +18,8 @@ mod utils;
 use frame_rate::FrameRate;
 use box_shape::Box;
 
-const FRAME_TIME: u32 = 1_000_000_000 / 60;
 fn main() {
-    #[cfg(target_os = "emscripten")]
-    stdweb::initialize();
-
-    let (width, height) = utils::get_window_dimention();
+    let (width, height) = main_loop::get_window_dimention();
 
     let ctx = sdl2::init().unwrap();
     let video = ctx.video().unwrap();
@@ -71,7 +60,7 @@ fn main() {
     let mut start_x = 0f32;
     let mut start_y = 0f32;
     let mut frame_rate = FrameRate::new(100);
-    let mut main_loop = || {
+    main_loop::run_main_loop(move || {
         frame_rate.tick();
 
         box0.update();
@@ -80,7 +69,7 @@ fn main() {
         for event in events.poll_iter() {
             match event {
                 Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
-                    process::exit(1);
+                    return LoopControl::Exit;
                 },
                 Event::KeyDown { keycode: Some(Keycode::Left), ..} => {
                     let x = box0.x() - 10f32;
@@ -166,27 +155,6 @@ fn main() {
         let _ = canvas.string(10, 10, frame_rate.mean().to_string().as_str(), green);
 
         canvas.present();
-    };
-
-    #[cfg(target_os = "emscripten")]
-    use emscripten::{emscripten};
-
-    #[cfg(target_os = "emscripten")]
-    emscripten::set_main_loop_callback(main_loop);
-
-    #[cfg(not(target_os = "emscripten"))]
-    {
-        let frame_time = Duration::new(0, FRAME_TIME);
-        loop {
-            let frame_start = Instant::now();
-
-            main_loop();
-
-            let draw_time = Instant::now().duration_since(frame_start);
-            if frame_time > draw_time {
-                // framerate control
-                sleep(frame_time - draw_time);
-            }
-        }
-    }
+        LoopControl::Continue
+    });
 }
@@ -0,0 +1,55 @@
+// Make thumbnails for a directory of images and write its gallery manifest.
+//
+// usage: gallery-manifest <image dir> [-o manifest.json] [--size 200] [--thumbs thumbs] [--force]
+extern crate sdl2_gallery;
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use sdl2_gallery::manifest::{self, ManifestOptions};
+
+/// exits with `code`, 0 when help was asked for
+fn usage(code: i32) -> ! {
+    eprintln!("usage: gallery-manifest <image dir> [-o manifest.json] [--size 200] [--thumbs thumbs] [--force]");
+    process::exit(code);
+}
+
+fn main() {
+    let mut options = ManifestOptions::default();
+    let mut dir = None;
+    let mut out = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => out = Some(PathBuf::from(args.next().unwrap_or_else(|| usage(1)))),
+            "--size" => {
+                options.thumb_size = args.next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&s| s > 0)
+                    .unwrap_or_else(|| usage(1));
+            },
+            "--thumbs" => options.thumb_dir = args.next().unwrap_or_else(|| usage(1)),
+            "--force" => options.skip_existing = false,
+            "-h" | "--help" => usage(0),
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => usage(1),
+        }
+    }
+    let dir = dir.unwrap_or_else(|| usage(1));
+    // manifest urls are relative to the image dir, keep it there by default
+    let out = out.unwrap_or(dir.join("gallery.json"));
+
+    let gallery = match manifest::build_manifest(&dir, &options) {
+        Ok(gallery) => gallery,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    };
+    if let Err(e) = manifest::write_manifest(&gallery, &out) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    println!("{} images written to {}", gallery.pics.len(), out.display());
+}
@@ -0,0 +1,97 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// source of time for animations,
+/// so they can be driven by a virtual clock when replaying
+pub trait Clock {
+    /// time passed since the clock is created
+    fn now(&self) -> Duration;
+}
+
+pub struct RealClock {
+    start: Instant,
+}
+
+impl RealClock {
+    pub fn new() -> RealClock {
+        RealClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// a clock that only moves when told to
+pub struct ManualClock {
+    now: Cell<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock {
+            now: Cell::new(Duration::from_millis(0)),
+        }
+    }
+    pub fn set(&self, now: Duration) {
+        self.now.set(now);
+    }
+    pub fn advance(&self, d: Duration) {
+        self.now.set(self.now.get() + d);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+/// physics constants are tuned for 60fps, speeds are px per such frame
+const FRAME_TIME: f32 = 1. / 60.;
+/// limit time step, so a long pause does not jump the animation
+const MAX_FRAMES: f32 = 5.;
+
+/// number of 60fps frames in `d`
+pub fn frames(d: Duration) -> f32 {
+    (d.as_secs() as f32 + d.subsec_nanos() as f32 * 1e-9) / FRAME_TIME
+}
+
+/// number of 60fps frames between two event timestamps in ms
+pub fn frames_between(timestamp0: u32, timestamp: u32) -> f32 {
+    // at least 1ms, events may share a timestamp
+    frames(Duration::from_millis(timestamp.saturating_sub(timestamp0).max(1) as u64))
+}
+
+/// `d` in ms, to compare with event timestamps.
+/// the stage clock starts with the app, as sdl ticks do
+pub fn timestamp(d: Duration) -> u32 {
+    (d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000) as u32
+}
+
+/// measure time step between updates
+pub struct FrameTimer {
+    clock: Rc<Clock>,
+    last: Duration,
+}
+
+impl FrameTimer {
+    pub fn new(clock: Rc<Clock>) -> FrameTimer {
+        FrameTimer {
+            last: clock.now(),
+            clock,
+        }
+    }
+    /// frames passed since last tick
+    pub fn tick(&mut self) -> f32 {
+        let now = self.clock.now();
+        let d = frames(now - self.last);
+        self.last = now;
+        d.min(MAX_FRAMES)
+    }
+}
@@ -1,50 +1,320 @@
-use std::collections::HashMap;
+use std::fmt;
+#[cfg(not(target_os = "emscripten"))]
+use std::mem;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+use serde_json;
+use toml;
 use model::Gallery;
-use stdweb::unstable::TryInto;
-use utils;
+use fetch;
+use main_loop;
 
-lazy_static! {
-    pub static ref CONFIG: Config = Config::new();
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    Invalid(String),
 }
 
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ConfigError::Io(ref s) => write!(f, "cannot read config: {}", s),
+            &ConfigError::Parse(ref s) => write!(f, "cannot parse config: {}", s),
+            &ConfigError::Invalid(ref s) => write!(f, "invalid config: {}", s),
+        }
+    }
+}
+
+/// app settings, missing fields in a config file take default values
+#[derive(Deserialize, Debug)]
+#[serde(default)]
 pub struct Config {
-    fields: HashMap<&'static str, u32>,
+    /// window size, 0 to fit the browser window
+    pub width: u32,
+    pub height: u32,
+    pub thumb_width: u32,
+    pub thumb_height: u32,
+    pub thumb_gap: u32,
+    /// slide speed lost each 60fps frame, in px
+    pub friction: f32,
+    pub transition_ms: u64,
+    /// shown while an image is loading
+    pub default_image: String,
+    /// memory for image textures, least recently shown ones are dropped past it
+    pub texture_budget_mb: u32,
+    /// failed image loads are retried this many times,
+    /// first after `retry_delay_ms`, then twice as long each time
+    pub retry_max: u32,
+    pub retry_delay_ms: u64,
+    /// images fetched at once
+    pub max_loads: u32,
+    /// fetches taking longer fail, 0 waits forever
+    pub fetch_timeout_ms: u64,
+    /// thumbnail rows loaded ahead in scroll direction
+    pub prefetch_rows: u32,
+    pub gallery: Gallery,
+    /// load gallery manifest from here instead of `gallery`
+    pub gallery_url: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            width: 0,
+            height: 0,
+            thumb_width: 100,
+            thumb_height: 100,
+            thumb_gap: 10,
+            friction: 2.,
+            transition_ms: 300,
+            default_image: "assets/iconmonstr-picture-1-240.png".to_owned(),
+            texture_budget_mb: 64,
+            retry_max: 3,
+            retry_delay_ms: 1000,
+            max_loads: 4,
+            fetch_timeout_ms: 30000,
+            prefetch_rows: 2,
+            gallery: Gallery::default(),
+            gallery_url: None,
+        }
+    }
 }
 
 impl Config {
-    pub fn new() -> Config {
-        let mut fields = HashMap::new();
-        let (width, height) = utils::get_window_dimention();
-        unsafe {
-            fields.insert("width", width);
-            fields.insert("height", height);
-            fields.insert("gallery", Box::into_raw(Box::new(get_gallery())) as u32);
+    pub fn from_json(s: &str) -> Result<Config, ConfigError> {
+        serde_json::from_str(s).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+    pub fn from_toml(s: &str) -> Result<Config, ConfigError> {
+        toml::from_str(s).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+    /// parse by extension of `name`, json is the default
+    pub fn from_str(name: &str, s: &str) -> Result<Config, ConfigError> {
+        if name.ends_with(".toml") {
+            Config::from_toml(s)
+        } else {
+            Config::from_json(s)
         }
+    }
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let s = read_file(path)?;
+        Config::from_str(&path.to_string_lossy(), &s)
+    }
 
-        Config {
-            fields
+    /// read config from `Module.config`, or the old style `Module.gallery`
+    #[cfg(target_os = "emscripten")]
+    pub fn from_module() -> Result<Config, ConfigError> {
+        use stdweb::unstable::TryInto;
+        let s: String = js! {
+            return JSON.stringify(Module.config || {gallery: Module.gallery || {pics: []}});
+        }.try_into().map_err(|_| ConfigError::Parse("Module.config is not an object".to_owned()))?;
+        Config::from_json(&s)
+    }
+
+    /// load config from `Module.configUrl` if set, or from `Module`
+    #[cfg(target_os = "emscripten")]
+    pub fn load<F>(cbk: F)
+        where F: FnOnce(Result<Config, ConfigError>) + 'static {
+        use stdweb::unstable::TryInto;
+        let url: Option<String> = js! {
+            return Module.configUrl;
+        }.try_into().ok();
+        match url {
+            Some(url) => {
+                let url2 = url.clone();
+                fetch_text(&url, move |result| {
+                    match result.and_then(|s| Config::from_str(&url2, &s)) {
+                        Ok(config) => config.resolve(cbk),
+                        Err(e) => cbk(Err(e)),
+                    }
+                });
+            },
+            None => {
+                match Config::from_module() {
+                    Ok(config) => config.resolve(cbk),
+                    Err(e) => cbk(Err(e)),
+                }
+            },
         }
     }
-    pub fn get_instance() -> &'static Config {
-        &CONFIG
+
+    /// load config from the path given as first argument, or the images in `static/img`
+    #[cfg(not(target_os = "emscripten"))]
+    pub fn load<F>(cbk: F)
+        where F: FnOnce(Result<Config, ConfigError>) + 'static {
+        use std::env;
+        let path = env::args().nth(1).unwrap_or("static/img".to_owned());
+        match Config::from_path(&path) {
+            Ok(config) => config.resolve(cbk),
+            Err(e) => cbk(Err(e)),
+        }
     }
-    pub fn get_u32(key: &'static str) -> Option<&'static u32> {
-        CONFIG.fields.get(key)
+
+    /// `path` is a json/toml config file, an image directory,
+    /// or a text file listing one image each line,
+    /// relative image paths are taken from the directory of the file
+    #[cfg(not(target_os = "emscripten"))]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new("")).to_owned();
+        let io_err = |e: ::std::io::Error| ConfigError::Io(format!("{}: {}", path.display(), e));
+        if path.is_dir() {
+            let gallery = Gallery::from_dir(path).map_err(io_err)?;
+            return Ok(Config { gallery, ..Default::default() });
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") | Some("toml") => {
+                let mut config = Config::from_file(path)?;
+                config.gallery = mem::replace(&mut config.gallery, Gallery::default()).with_base(&base);
+                config.gallery_url = config.gallery_url.map(|url| base.join(url).to_string_lossy().into_owned());
+                // a bare manifest written by gallery-manifest
+                if config.gallery.pics.is_empty() && config.gallery_url.is_none() {
+                    config.gallery_url = Some(path.to_string_lossy().into_owned());
+                }
+                Ok(config)
+            },
+            _ => {
+                let gallery = Gallery::from_list(path).map_err(io_err)?;
+                Ok(Config { gallery, ..Default::default() })
+            },
+        }
     }
-    pub fn get_gallery() -> Option<&'static Gallery> {
-        if let Some(&v) = CONFIG.fields.get("gallery") {
-            unsafe {
-                let g: &Gallery = &*(v as *const Gallery);
-                return Some(g);
-            }
+
+    /// fill in window size, load gallery from `gallery_url`, then validate
+    pub fn resolve<F>(mut self, cbk: F)
+        where F: FnOnce(Result<Config, ConfigError>) + 'static {
+        let (width, height) = main_loop::get_window_dimention();
+        if self.width == 0 {
+            self.width = width;
+        }
+        if self.height == 0 {
+            self.height = height;
+        }
+
+        match self.gallery_url.take() {
+            Some(url) => {
+                load_gallery(&url, move |result| {
+                    cbk(result.and_then(|gallery| {
+                        self.gallery = gallery;
+                        self.validate().map(|_| self)
+                    }));
+                });
+            },
+            None => {
+                cbk(self.validate().map(|_| self));
+            },
         }
-        None
     }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(ConfigError::Invalid("width and height should not be 0".to_owned()));
+        }
+        if self.thumb_width == 0 || self.thumb_height == 0 {
+            return Err(ConfigError::Invalid("thumb_width and thumb_height should not be 0".to_owned()));
+        }
+        if self.thumb_width + 2 * self.thumb_gap > self.width {
+            return Err(ConfigError::Invalid(format!("thumbnails do not fit in width {}", self.width)));
+        }
+        if self.max_loads == 0 {
+            return Err(ConfigError::Invalid("max_loads should not be 0".to_owned()));
+        }
+        if !(self.friction > 0.) {
+            return Err(ConfigError::Invalid("friction should be positive".to_owned()));
+        }
+        if self.gallery.pics.is_empty() {
+            return Err(ConfigError::Invalid("gallery has no pictures".to_owned()));
+        }
+        Ok(())
+    }
+
+    pub fn transition_duration(&self) -> Duration {
+        Duration::from_millis(self.transition_ms)
+    }
+
+    pub fn fetch_timeout(&self) -> Option<Duration> {
+        if self.fetch_timeout_ms > 0 {
+            Some(Duration::from_millis(self.fetch_timeout_ms))
+        } else {
+            None
+        }
+    }
+}
+
+fn read_file<P: AsRef<Path>>(path: P) -> Result<String, ConfigError> {
+    let mut s = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut s))
+        .map_err(|e| ConfigError::Io(e.to_string()))?;
+    Ok(s)
+}
+
+fn parse_gallery(s: &str) -> Result<Gallery, ConfigError> {
+    serde_json::from_str(s).map_err(|e| ConfigError::Parse(e.to_string()))
 }
 
-fn get_gallery() -> Gallery {
-    let gallery = js! {
-        return Module.gallery;
-    };
-    gallery.try_into().unwrap()
-}
\ No newline at end of file
+/// a url on the web, a local path natively
+fn fetch_text<F>(url: &str, cbk: F)
+    where F: FnOnce(Result<String, ConfigError>) + 'static {
+    let url2 = url.to_owned();
+    fetch::fetch_bytes(url, move |result| {
+        cbk(result
+            .map_err(|e| ConfigError::Io(format!("cannot fetch {}: {}", url2, e)))
+            .and_then(|rsp| String::from_utf8(rsp.body)
+                .map_err(|_| ConfigError::Parse(format!("{} is not utf-8", url2)))));
+    });
+}
+
+/// image urls in the manifest are relative to it
+fn load_gallery<F>(url: &str, cbk: F)
+    where F: FnOnce(Result<Gallery, ConfigError>) + 'static {
+    let base = Path::new(url).parent().unwrap_or(Path::new("")).to_owned();
+    fetch_text(url, move |result| {
+        cbk(result.and_then(|s| parse_gallery(&s)).map(|g| g.with_base(&base)));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_json_and_toml() {
+        let c = Config::from_json(r#"{"width": 320, "friction": 3.5,
+            "gallery": {"pics": [{"url": "a.jpg", "preview": "a_s.jpg"}]}}"#).unwrap();
+        assert_eq!(c.width, 320);
+        assert_eq!(c.friction, 3.5);
+        assert_eq!(c.thumb_width, 100);
+        assert_eq!(c.gallery.pics[0].preview, "a_s.jpg");
+
+        let c = Config::from_str("gallery.toml", r#"
+            thumb_gap = 4
+            gallery_url = "pics.json"
+        "#).unwrap();
+        assert_eq!(c.thumb_gap, 4);
+        assert_eq!(c.gallery_url, Some("pics.json".to_owned()));
+
+        match Config::from_json("{\"width\": -1}") {
+            Err(ConfigError::Parse(_)) => (),
+            r => panic!("expect parse error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut c = Config::from_json(r#"{"width": 320, "height": 480,
+            "gallery": {"pics": [{"url": "a.jpg", "preview": "a_s.jpg"}]}}"#).unwrap();
+        assert!(c.validate().is_ok());
+        c.thumb_width = 400;
+        assert!(c.validate().is_err());
+        c.thumb_width = 100;
+        c.gallery.pics.clear();
+        match c.validate() {
+            Err(ConfigError::Invalid(_)) => (),
+            r => panic!("expect invalid, got {:?}", r),
+        }
+    }
+}
@@ -1,108 +1,134 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
-use std::collections::{HashMap, HashSet};
 use std::default::Default;
-use std::marker::{Send};
 use std::ffi::{CString};
-use std::sync::Mutex;
-use std::any::Any;
 use std::fs::File;
 use std::io::prelude::*;
-use sdl2::video::{Window, WindowContext};
-use sdl2::image::{LoadSurface};
+use sdl2::video::Window;
 use sdl2::render::{Canvas, TextureCreator};
-use sdl2::surface::Surface;
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::pixels::{Color};
 use sdl2::rect::{Rect, Point};
-use stdweb::web::TypedArray;
 
 use config::{Config};
-use utils::{self, SizedTexture};
+use utils::SizedTexture;
 use actions::Action;
-use gesture::{GestureDetector, GestureEvent, GestureDetectorTypes};
-
-static mut TEXTURE_CREATOR: Option<TextureCreator<WindowContext>> = None;
-lazy_static!{
-    static ref LOAD_REGISTER: Mutex<HashMap<String, SizedTexture>> = Mutex::new(HashMap::new());
-    static ref LOADING_IMGS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
-}
+use navigation::{Navigator, ActionRouter, Route, Scene};
+use scene_transition::SceneTransition;
+use clock::{Clock, RealClock};
+use transition::Transition;
+use gesture::{GestureDetector, GestureEvent, GestureDetectorTypes, GestureConfig};
+use texture_cache::TextureCache;
+use resources::{Resources, LOCAL_IMG_PREFIX};
+pub use resources::LoadState;
 
 pub trait Display {
     fn render(&self, canvas: &mut Canvas<Window>, rect: Rect);
     fn handle_events(&mut self, event: &Event) -> Option<Action> { None }
     fn is_interactive(&self) -> bool { false }
     fn update(&mut self) {}
-    fn on_start(&mut self, &Option<Box<Any>>) {}
+    /// place children in `rect`, called again when the window resizes
+    fn layout(&mut self, _rect: Rect) {}
+    /// popped from the navigation stack or replaced
+    fn on_stop(&mut self) {}
+    /// another scene was pushed above
+    fn on_pause(&mut self) {}
+    /// active again after the scene above was popped
+    fn on_resume(&mut self) {}
 }
 
 pub struct Stage {
-    children: HashMap<String, Rc<RefCell<Display>>>,
-    active_scene: Option<Rc<RefCell<Display>>>,
-    params: Option<Box<Any>>,
+    navigator: Navigator,
+    router: ActionRouter,
+    /// running scene change
+    transition: Option<SceneTransition>,
+    clock: Rc<Clock>,
+    config: Rc<Config>,
+    resources: Resources,
 }
 
 impl Stage {
-    pub fn new(tc: TextureCreator<WindowContext>) -> Rc<RefCell<Stage>> {
-        unsafe {
-            TEXTURE_CREATOR = Some(tc);
-        }
+    pub fn new<T: 'static>(tc: TextureCreator<T>, config: Rc<Config>) -> Rc<RefCell<Stage>> {
+        Stage::with_clock(tc, config, Rc::new(RealClock::new()))
+    }
+    /// all animations in the stage are timed by `clock`,
+    /// textures come from `tc`, of a window or an offscreen canvas
+    pub fn with_clock<T: 'static>(tc: TextureCreator<T>, config: Rc<Config>, clock: Rc<Clock>) -> Rc<RefCell<Stage>> {
         Rc::new(RefCell::new(Stage {
-            children: HashMap::new(),
-            active_scene: None,
-            params: None,
+            navigator: Navigator::new(),
+            router: ActionRouter::new(),
+            transition: None,
+            clock,
+            resources: Resources::new(tc, config.clone()),
+            config,
         }))
     }
-    pub fn add_scene(&mut self, key: &str, c: Rc<RefCell<Display>>) {
-        self.children.insert(key.to_owned(), c);
+    pub fn clock(&self) -> Rc<Clock> {
+        self.clock.clone()
     }
-    pub fn start(&mut self, key: &str) {
-        if let Some(s) = self.children.get(key) {
-            let s = s.clone();
-            {
-                let params = self.get_params();
-                s.borrow_mut().on_start(params);
-            }
-            self.active_scene = Some(s);
-        }
+    pub fn config(&self) -> Rc<Config> {
+        self.config.clone()
     }
-    pub fn update(&self) {
-        if let Some(ref scene) = self.active_scene {
-            scene.borrow_mut().update();
-        }
+    /// textures and image loads shared by the scenes
+    pub fn resources(&self) -> Resources {
+        self.resources.clone()
     }
-    pub fn set_params(&mut self, p: Box<Any>) {
-        self.params = Some(p);
+    pub fn add_scene<S: Scene + 'static>(&mut self, route: Route<S::Params>, scene: Rc<RefCell<S>>) {
+        self.navigator.add_scene(route, scene);
     }
-    pub fn get_params(&self) -> &Option<Box<Any>> {
-        &self.params
+    /// actions returned by the scenes go to `handler`, after the ones added before
+    pub fn add_action_handler<F>(&mut self, handler: F)
+        where F: FnMut(&Action, &mut Navigator) -> bool + 'static {
+        self.router.add(handler);
+    }
+    pub fn navigator(&mut self) -> &mut Navigator {
+        &mut self.navigator
     }
 }
 
 impl Display for Stage {
     fn render(&self, canvas: &mut Canvas<Window>, rect: Rect) {
-        if let Some(ref scene) = self.active_scene {
+        self.resources.begin_frame();
+        if let Some(ref t) = self.transition {
+            t.render(canvas, rect, &self.resources);
+        } else if let Some(scene) = self.navigator.active() {
             scene.borrow().render(canvas, rect.clone());
         }
     }
+    fn update(&mut self) {
+        self.resources.update_loads(self.clock.now());
+        if let Some(change) = self.navigator.take_change() {
+            let duration = self.config.transition_duration();
+            self.transition = Some(SceneTransition::new(change, duration, self.clock.clone()));
+        }
+        let running = match self.transition {
+            Some(ref mut t) => t.step(),
+            None => false,
+        };
+        if !running {
+            self.transition = None;
+        }
+        // the scene left stays as it was during the transition
+        if let Some(scene) = self.navigator.active() {
+            scene.borrow_mut().update();
+        }
+    }
+    /// the action if no handler took it
     fn handle_events(&mut self, event: &Event) -> Option<Action> {
-        let mut action = None;
-        if let Some(ref scene) = self.active_scene {
-            action = scene.borrow_mut().handle_events(&event);
+        if let &Event::Window { win_event: WindowEvent::SizeChanged(w, h), .. } = event {
+            self.layout(Rect::new(0, 0, w as u32, h as u32));
         }
-        if let Some(a) = action {
-            match a {
-                Action::ShowPreview(i) => {
-                    self.set_params(Box::new(i));
-                    self.start("preview");
-                },
-                Action::ShowGallery => {
-                    self.start("gallery");
-                },
-                _ => (),
-            }
+        let action = match self.navigator.active() {
+            Some(scene) => scene.borrow_mut().handle_events(&event),
+            None => None,
+        };
+        action.and_then(|a| self.router.route(a, &mut self.navigator))
+    }
+    /// every scene, so the ones not shown fit when they come back
+    fn layout(&mut self, rect: Rect) {
+        for scene in self.navigator.scenes() {
+            scene.borrow_mut().layout(rect);
         }
-        None
     }
 }
 
@@ -111,8 +137,6 @@ pub enum FillMode {
     Contain,
 }
 
-static mut DEFAULT_LOADED: bool = false;
-const DEFAULT_IMG: &'static str = "../assets/iconmonstr-picture-1-240.png";
 /// image from network are not loaded when you call load
 /// image from localdisk are loaded eagerly
 pub struct Image {
@@ -122,67 +146,98 @@ pub struct Image {
     h: u32,
     fill: FillMode,
     local: bool,
+    /// image size known before it loads
+    natural_size: Option<(u32, u32)>,
+    /// low-res image shown until `src` loads, then faded out
+    fallback: String,
+    fallback_shown: Cell<bool>,
+    fade: RefCell<Option<Transition<f32>>>,
+    clock: Option<Rc<Clock>>,
+    res: Resources,
 }
 
 impl Image {
-    pub fn new(src: String) -> Rc<RefCell<Image>> {
-        Rc::new(RefCell::new(Image {
+    fn with_resources(res: Resources, src: String, w: u32, h: u32) -> Image {
+        Image {
             dirty: false,
             src,
-            ..Default::default()
-        }))
+            w,
+            h,
+            fill: FillMode::Contain,
+            local: false,
+            natural_size: None,
+            fallback: "".to_string(),
+            fallback_shown: Cell::new(false),
+            fade: RefCell::new(None),
+            clock: None,
+            res,
+        }
     }
-    pub fn new_with_dimension_local(src: String, w: u32, h: u32) -> Image {
+    pub fn new(res: Resources, src: String) -> Rc<RefCell<Image>> {
+        Rc::new(RefCell::new(Image::with_resources(res, src, 0, 0)))
+    }
+    pub fn new_with_dimension_local(res: Resources, src: String, w: u32, h: u32) -> Image {
         if src != "" {
-            load_local_img(&src);
+            res.load_local_img(&src);
         }
         Image {
-            dirty: false,
-            src,
-            w,
-            h,
             local: true,
-            ..Default::default()
+            ..Image::with_resources(res, src, w, h)
         }
     }
-    pub fn new_with_dimension(src: String, w: u32, h: u32) -> Rc<RefCell<Image>> {
-        Rc::new(RefCell::new(Image {
-            dirty: false,
-            src,
-            w,
-            h,
-            ..Default::default()
-        }))
+    pub fn new_with_dimension(res: Resources, src: String, w: u32, h: u32) -> Rc<RefCell<Image>> {
+        Rc::new(RefCell::new(Image::with_resources(res, src, w, h)))
     }
     pub fn load(&self) {
+        self.load_with_priority(0);
+    }
+    /// lower `priority` loads first
+    pub fn load_with_priority(&self, priority: i64) {
         if self.src == "" {
             return;
         }
-        // load default image is not loaded
-        unsafe {
-            if !DEFAULT_LOADED {
-                load_local_img(DEFAULT_IMG);
-                DEFAULT_LOADED = true;
-            }
-        }
+        self.res.load_default_img();
         if self.local {
-            load_local_img(&self.src);
+            self.res.load_local_img(&self.src);
         } else {
-            load_img(&self.src);
+            // fallback is small, get it first
+            self.res.load_img_with_priority(&self.fallback, priority - 1);
+            self.res.load_img_with_priority(&self.src, priority);
+        }
+    }
+    /// forget the load, aborting its fetch if started
+    pub fn cancel_load(&self) {
+        if !self.local {
+            self.res.cancel_load(&self.src);
+        }
+    }
+    pub fn load_state(&self) -> LoadState {
+        if self.local {
+            return self.res.load_state(&(LOCAL_IMG_PREFIX.to_owned() + &self.src));
+        }
+        self.res.load_state(&self.src)
+    }
+    /// load again now if loading failed
+    pub fn retry(&self) {
+        if !self.local {
+            self.res.retry_img(&self.src);
         }
     }
     pub fn is_loaded(&self) -> bool {
         if self.local {
             return true;
         }
-        let m = LOAD_REGISTER.lock().unwrap();
-        m.get(&self.src).is_some()
+        self.res.is_loaded(&self.src)
     }
     pub fn get_src(&mut self) -> &str {
         &self.src
     }
     pub fn set_src(&mut self, src: &str) {
         self.src = src.to_string();
+        self.natural_size = None;
+        self.fallback = "".to_owned();
+        self.fallback_shown.set(false);
+        *self.fade.borrow_mut() = None;
         if self.local {
             self.load();
         }
@@ -190,46 +245,45 @@ impl Image {
     pub fn set_fill(&mut self, v: FillMode) {
         self.fill = v;
     }
+    /// show `src`, usually a cached thumbnail, until the image loads,
+    /// then cross-fade if a clock is set
+    pub fn set_fallback(&mut self, src: &str) {
+        self.fallback = src.to_owned();
+    }
+    pub fn set_clock(&mut self, clock: Rc<Clock>) {
+        self.clock = Some(clock);
+    }
+    pub fn set_natural_size(&mut self, size: Option<(u32, u32)>) {
+        self.natural_size = size;
+    }
+    /// size of loaded image, or the natural size if not loaded yet
     pub fn get_img_size(&self) -> Option<(u32, u32)> {
-        let m = LOAD_REGISTER.lock().unwrap();
-        if let Some(&SizedTexture(img_w, img_h, ..)) = m.get(&self.src) {
-            Some((img_w, img_h))
-        } else {
-            None
-        }
+        let m = self.res.textures();
+        m.peek(&self.src)
+            .map(|(img_w, img_h, _)| (img_w, img_h))
+            .or(self.natural_size)
+            // same aspect ratio, good enough for layout
+            .or_else(|| m.peek(&self.fallback).map(|(img_w, img_h, _)| (img_w, img_h)))
     }
 
-    pub fn cover_size(img_w: u32, img_h: u32, w: u32, h: u32) -> (u32, u32) {
-        let img_r = img_w as f64 / img_h as f64;
-        let r = w as f64 / h as f64;
-        if img_r > r {
-            ((h as f64 * img_r) as u32, h)
-        } else {
-            (w, (w as f64 / img_r) as u32)
+    /// opacity of the loaded image, fades in if the fallback was shown before
+    fn fade_alpha(&self, has_fallback: bool) -> f32 {
+        let mut fade = self.fade.borrow_mut();
+        if fade.is_none() && self.fallback_shown.get() && has_fallback {
+            if let Some(ref clock) = self.clock {
+                *fade = Some(Transition::new(0., 1., self.res.config().transition_duration(), clock.clone()));
+            }
+            self.fallback_shown.set(false);
         }
-    }
-
-    pub fn contain_size(img_w: u32, img_h: u32, w: u32, h: u32) -> (u32, u32) {
-        let img_r = img_w as f64 / img_h as f64;
-        let r = w as f64 / h as f64;
-        if img_r > r {
-            (w, (w as f64 / img_r) as u32)
-        } else {
-            ((img_r * h as f64) as u32, h)
+        match *fade {
+            Some(ref mut t) => t.step(),
+            None => 1.,
         }
     }
-}
 
-impl Display for Image {
-    fn render(&self, canvas: &mut Canvas<Window>, rect: Rect) {
-        if self.src == "" {
-            return;
-        }
-        let m = LOAD_REGISTER.lock().unwrap();
-        let prefix = if self.local { LOCAL_IMG_PREFIX } else { "" };
-        let src = prefix.to_owned() + &self.src;
-        let texture = m.get(&src).or_else(|| m.get(&(LOCAL_IMG_PREFIX.to_owned() + DEFAULT_IMG)));
-        if let Some(&SizedTexture(img_w, img_h, ref tex)) = texture {
+    fn render_texture(&self, canvas: &mut Canvas<Window>, rect: Rect,
+                      m: &mut TextureCache<SizedTexture>, key: &str, alpha: u8) {
+        if let Some((img_w, img_h, &mut SizedTexture(_, _, ref mut tex))) = m.peek_mut(key) {
             let s_rect = Rect::new(0, 0, img_w, img_h);
 
             // work out render size
@@ -246,6 +300,7 @@ impl Display for Image {
                                    (rect.height() as i32 - h as i32) / 2 + rect.y(),
                                    w, h);
 
+            tex.set_alpha_mod(alpha);
             canvas.set_clip_rect(rect);
             let _ = canvas.copy(tex,
                                 s_rect,
@@ -253,58 +308,81 @@ impl Display for Image {
             canvas.set_clip_rect(None);
         }
     }
-}
 
-impl Default for Image {
-    fn default() -> Self {
-        Self {
-            dirty: false,
-            src: "".to_string(),
-            w: 0,
-            h: 0,
-            fill: FillMode::Contain,
-            local: false,
-        }
+    /// crossed box, unlike the default image shown while loading
+    fn render_error(canvas: &mut Canvas<Window>, rect: Rect) {
+        let size = (rect.width().min(rect.height()) / 3).max(8);
+        let r = Rect::from_center(rect.center(), size, size);
+        canvas.set_clip_rect(rect);
+        canvas.set_draw_color(Color::RGB(200, 60, 60));
+        let _ = canvas.draw_rect(r);
+        let _ = canvas.draw_line(r.top_left(), r.bottom_right());
+        let _ = canvas.draw_line(r.top_right(), r.bottom_left());
+        canvas.set_clip_rect(None);
     }
-}
 
-pub fn load_img(src: &str) {
-    if src == "" {
-        return;
-    }
-    if LOADING_IMGS.lock().unwrap().contains(src) {
-        return;
-    }
-    // check if already loaded
-    let m = LOAD_REGISTER.lock().unwrap();
-    if m.get(src).is_some() {
-        return;
+    pub fn cover_size(img_w: u32, img_h: u32, w: u32, h: u32) -> (u32, u32) {
+        let img_r = img_w as f64 / img_h as f64;
+        let r = w as f64 / h as f64;
+        if img_r > r {
+            ((h as f64 * img_r) as u32, h)
+        } else {
+            (w, (w as f64 / img_r) as u32)
+        }
     }
 
-    LOADING_IMGS.lock().unwrap().insert(src.to_owned());
-    let src2 = src.to_owned();
-    let src3 = src.to_owned();
-    utils::fetch(src, move |file| {
-        loaded(&src2, &file)
-    }, move || {
-        load_err(&src3);
-    });
+    pub fn contain_size(img_w: u32, img_h: u32, w: u32, h: u32) -> (u32, u32) {
+        let img_r = img_w as f64 / img_h as f64;
+        let r = w as f64 / h as f64;
+        if img_r > r {
+            (w, (w as f64 / img_r) as u32)
+        } else {
+            ((img_r * h as f64) as u32, h)
+        }
+    }
 }
 
-const LOCAL_IMG_PREFIX: &'static str = "!local:";
-pub fn load_local_img(file: &str) {
-    let src = LOCAL_IMG_PREFIX.to_owned() + file;
-    // check if already loaded
-    let m = LOAD_REGISTER.lock().unwrap();
-    if m.get(&src).is_some() {
-        return;
-    }
+impl Display for Image {
+    fn render(&self, canvas: &mut Canvas<Window>, rect: Rect) {
+        if self.src == "" {
+            return;
+        }
+        if let LoadState::Failed(_) = self.load_state() {
+            Self::render_error(canvas, rect);
+            return;
+        }
+        let opacity = self.res.opacity();
+        let mut m = self.res.textures();
+        let prefix = if self.local { LOCAL_IMG_PREFIX } else { "" };
+        let src = prefix.to_owned() + &self.src;
+        let missing = m.get(&src).is_none();
+        // load again if it was dropped from cache while out of view
+        let reload = missing && !self.local && m.was_evicted(&src);
+        let has_fallback = self.fallback != "" && m.get(&self.fallback).is_some();
 
-    loaded(&src, file);
+        if missing && has_fallback {
+            self.fallback_shown.set(true);
+            self.render_texture(canvas, rect, &mut m, &self.fallback, (opacity * 255.) as u8);
+        } else if missing {
+            let key = LOCAL_IMG_PREFIX.to_owned() + &self.res.config().default_image;
+            self.render_texture(canvas, rect, &mut m, &key, (opacity * 255.) as u8);
+        } else {
+            let alpha = self.fade_alpha(has_fallback);
+            if alpha < 1. {
+                self.render_texture(canvas, rect, &mut m, &self.fallback, (opacity * 255.) as u8);
+            }
+            self.render_texture(canvas, rect, &mut m, &src, (alpha * opacity * 255.) as u8);
+        }
+        drop(m);
+        if reload {
+            self.res.load_img(&self.src);
+        }
+    }
 }
 
 pub struct Button {
     rect: Rect,
+    screen_size: (u32, u32),
     active_img: Option<Image>,
     active_color: Option<Color>,
     img: Option<Image>,
@@ -313,25 +391,40 @@ pub struct Button {
 }
 
 impl Button {
-    pub fn new(rect: Rect) -> Button {
+    /// `screen_size` until the first layout
+    pub fn new(rect: Rect, (width, height): (u32, u32)) -> Button {
         Button {
             rect,
+            screen_size: (width, height),
             active_color: None,
             active_img: None,
             img: None,
             color: None,
-            gesture_detector: GestureDetector::new(vec![GestureDetectorTypes::Tap]),
+            gesture_detector: GestureDetector::with_config(
+                vec![GestureDetectorTypes::Tap],
+                GestureConfig { screen_size: Some((width, height)), ..Default::default() }),
         }
     }
     pub fn set_img(&mut self, img: Image) {
         self.img = Some(img);
     }
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+    /// taps are in screen ratio
+    pub fn set_screen_size(&mut self, size: (u32, u32)) {
+        self.screen_size = size;
+        self.gesture_detector.set_screen_size(size);
+    }
 }
 
 impl Display for Button {
+    /// `rect` is the parent's, the button is placed in it
     fn render(&self, canvas: &mut Canvas<Window>, rect: Rect) {
         if let Some(ref img) = self.img {
-            img.render(canvas, self.rect);
+            let mut r = self.rect;
+            r.offset(rect.x(), rect.y());
+            img.render(canvas, r);
         }
     }
     fn handle_events(&mut self, evt: &Event) -> Option<Action> {
@@ -341,8 +434,8 @@ impl Display for Button {
         for ref event in self.gesture_detector.poll() {
             match event {
                 &GestureEvent::Tap(x, y) => {
-                    let x = x * (*Config::get_u32("width").unwrap()) as f32;
-                    let y = y * (*Config::get_u32("height").unwrap()) as f32;
+                    let x = x * self.screen_size.0 as f32;
+                    let y = y * self.screen_size.1 as f32;
 
                     if self.rect.contains_point(Point::new(x as i32, y as i32)) {
                         return Some(Action::ShowGallery);
@@ -357,30 +450,3 @@ impl Display for Button {
         true
     }
 }
-
-fn loaded(src: &str, file: &str) {
-    unsafe {
-        let mut m = LOAD_REGISTER.lock().unwrap();
-        LOADING_IMGS.lock().unwrap().remove(src);
-
-        if let Ok(surf) = Surface::from_file(file) {
-            if let Some(ref tc) = TEXTURE_CREATOR {
-                let w = surf.width();
-                let h = surf.height();
-                let tex = tc.create_texture_from_surface(surf).expect("failed to create texture fron surface");
-                m.insert(src.to_owned(), SizedTexture(w, h, tex));
-            } else {
-                println!("load err");
-            }
-        } else {
-            println!("not image");
-        }
-    }
-}
-
-fn load_err(src: &str) {
-    unsafe {
-        LOADING_IMGS.lock().unwrap().remove(src);
-        println!("load failed! src: {}", src);
-    }
-}
@@ -0,0 +1,330 @@
+// Download bytes straight into rust memory.
+// On the web this is the browser `fetch`, with a timeout and abort through
+// an `AbortController`. Natively urls are local files, or `http://` urls
+// read with a blocking request, and the callback runs right away.
+use std::fmt;
+use std::time::Duration;
+#[cfg(target_os = "emscripten")]
+use std::cell::RefCell;
+#[cfg(target_os = "emscripten")]
+use std::rc::Rc;
+#[cfg(target_os = "emscripten")]
+use stdweb::{Once, Value};
+#[cfg(target_os = "emscripten")]
+use stdweb::web::TypedArray;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FetchError {
+    /// the server answered, but not with 2xx
+    Status(u16, String),
+    Network(String),
+    Timeout,
+    /// only on the web, native fetches can not be aborted
+    #[cfg_attr(not(target_os = "emscripten"), allow(dead_code))]
+    Aborted,
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &FetchError::Status(status, ref text) => write!(f, "http status {} {}", status, text),
+            &FetchError::Network(ref s) => write!(f, "network error: {}", s),
+            &FetchError::Timeout => write!(f, "timed out"),
+            &FetchError::Aborted => write!(f, "aborted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// names in lower case
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers.iter().find(|h| h.0 == name).map(|h| h.1.as_str())
+    }
+}
+
+/// "name: value" lines to pairs
+fn parse_headers(s: &str) -> Vec<(String, String)> {
+    s.lines()
+        .filter_map(|line| {
+            let i = line.find(':')?;
+            Some((line[..i].trim().to_lowercase(), line[i + 1..].trim().to_owned()))
+        })
+        .collect()
+}
+
+/// stops a running fetch, its callback gets `FetchError::Aborted`
+pub struct FetchHandle {
+    #[cfg(target_os = "emscripten")]
+    controller: Value,
+}
+
+impl FetchHandle {
+    #[cfg(target_os = "emscripten")]
+    pub fn abort(&self) {
+        js! { @(no_return)
+            var c = @{&self.controller};
+            if (c) {
+                c.abort();
+            }
+        };
+    }
+
+    /// native fetches are done once started
+    #[cfg(not(target_os = "emscripten"))]
+    pub fn abort(&self) {}
+}
+
+pub fn fetch_bytes<F>(url: &str, cbk: F) -> FetchHandle
+    where F: FnOnce(Result<Response, FetchError>) + 'static {
+    fetch_bytes_with_timeout(url, None, cbk)
+}
+
+#[cfg(target_os = "emscripten")]
+pub fn fetch_bytes_with_timeout<F>(url: &str, timeout: Option<Duration>, cbk: F) -> FetchHandle
+    where F: FnOnce(Result<Response, FetchError>) + 'static {
+    let timeout_ms = timeout.map(|t| (t.as_secs() * 1000 + t.subsec_nanos() as u64 / 1_000_000) as f64);
+    // only one of them is called, share the callback between them
+    let cbk = Rc::new(RefCell::new(Some(cbk)));
+    let cbk2 = cbk.clone();
+    let ok = move |status: u16, headers: String, body: TypedArray<u8>| {
+        if let Some(cbk) = cbk.borrow_mut().take() {
+            cbk(Ok(Response { status, headers: parse_headers(&headers), body: body.to_vec() }));
+        }
+    };
+    let err = move |kind: String, status: u16, text: String| {
+        if let Some(cbk) = cbk2.borrow_mut().take() {
+            cbk(Err(match kind.as_str() {
+                "status" => FetchError::Status(status, text),
+                "timeout" => FetchError::Timeout,
+                "abort" => FetchError::Aborted,
+                _ => FetchError::Network(text),
+            }));
+        }
+    };
+    let controller = js! {
+        var ok = @{Once(ok)};
+        var err = @{Once(err)};
+        var timeout = @{timeout_ms};
+        var ctrl = typeof AbortController !== "undefined" ? new AbortController() : null;
+        var timedOut = false;
+        var timer = timeout === null ? null : setTimeout(function () {
+            timedOut = true;
+            if (ctrl) {
+                ctrl.abort();
+            }
+            err("timeout", 0, "");
+            ok.drop();
+        }, timeout);
+        fetch(@{url}, ctrl ? {signal: ctrl.signal} : {})
+            .then(function (rsp) {
+                if (!rsp.ok) {
+                    var e = new Error(rsp.statusText);
+                    e.status = rsp.status;
+                    throw e;
+                }
+                var headers = "";
+                rsp.headers.forEach(function (value, name) {
+                    headers += name + ": " + value + "\n";
+                });
+                return rsp.arrayBuffer().then(function (ab) {
+                    clearTimeout(timer);
+                    ok(rsp.status, headers, new Uint8Array(ab));
+                    err.drop();
+                });
+            })
+            .catch(function (e) {
+                clearTimeout(timer);
+                if (timedOut) {
+                    return;
+                }
+                if (e.status) {
+                    err("status", e.status, e.message);
+                } else if (e.name === "AbortError") {
+                    err("abort", 0, "");
+                } else {
+                    err("network", 0, e.message || String(e));
+                }
+                ok.drop();
+            });
+        return ctrl;
+    };
+    FetchHandle { controller }
+}
+
+/// `url` is a local path, or `http://host[:port]/path`
+#[cfg(not(target_os = "emscripten"))]
+pub fn fetch_bytes_with_timeout<F>(url: &str, timeout: Option<Duration>, cbk: F) -> FetchHandle
+    where F: FnOnce(Result<Response, FetchError>) + 'static {
+    if url.starts_with("http://") {
+        cbk(native::get(url, timeout));
+    } else {
+        cbk(native::read_file(url));
+    }
+    FetchHandle {}
+}
+
+#[cfg(not(target_os = "emscripten"))]
+mod native {
+    use std::fs::File;
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::net::{TcpStream, ToSocketAddrs};
+    use std::path::Path;
+    use std::time::Duration;
+    use super::{parse_headers, FetchError, Response};
+
+    fn content_type(path: &str) -> &'static str {
+        match Path::new(path).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()) {
+            Some(ref e) if e == "jpg" || e == "jpeg" => "image/jpeg",
+            Some(ref e) if e == "png" => "image/png",
+            Some(ref e) if e == "gif" => "image/gif",
+            Some(ref e) if e == "json" => "application/json",
+            Some(ref e) if e == "toml" || e == "txt" => "text/plain",
+            _ => "application/octet-stream",
+        }
+    }
+
+    /// answers like a static file server would
+    pub fn read_file(path: &str) -> Result<Response, FetchError> {
+        let mut body = vec![];
+        match File::open(path).and_then(|mut f| f.read_to_end(&mut body)) {
+            Ok(_) => Ok(Response {
+                status: 200,
+                headers: vec![
+                    ("content-length".to_owned(), body.len().to_string()),
+                    ("content-type".to_owned(), content_type(path).to_owned()),
+                ],
+                body,
+            }),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Err(FetchError::Status(404, "Not Found".to_owned())),
+            Err(e) => Err(FetchError::Network(e.to_string())),
+        }
+    }
+
+    fn io_error(e: io::Error) -> FetchError {
+        match e.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => FetchError::Timeout,
+            _ => FetchError::Network(e.to_string()),
+        }
+    }
+
+    /// blocking HTTP/1.0 GET, the server closes the connection after the body
+    pub fn get(url: &str, timeout: Option<Duration>) -> Result<Response, FetchError> {
+        let rest = &url["http://".len()..];
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let addr_str = if host.contains(':') { host.to_owned() } else { format!("{}:80", host) };
+        let addr = addr_str.to_socket_addrs().map_err(io_error)?.next()
+            .ok_or(FetchError::Network(format!("cannot resolve {}", host)))?;
+        let mut stream = match timeout {
+            Some(t) => TcpStream::connect_timeout(&addr, t),
+            None => TcpStream::connect(addr),
+        }.map_err(io_error)?;
+        stream.set_read_timeout(timeout).map_err(io_error)?;
+        stream.set_write_timeout(timeout).map_err(io_error)?;
+        let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, host);
+        stream.write_all(request.as_bytes()).map_err(io_error)?;
+
+        let mut data = vec![];
+        stream.read_to_end(&mut data).map_err(io_error)?;
+        let split = data.windows(4).position(|w| w == b"\r\n\r\n")
+            .ok_or(FetchError::Network("bad http response".to_owned()))?;
+        let head = String::from_utf8_lossy(&data[..split]).into_owned();
+        let body = data[split + 4..].to_vec();
+
+        let mut lines = head.splitn(2, "\r\n");
+        let status_line = lines.next().unwrap_or("");
+        // HTTP/1.0 200 OK
+        let mut parts = status_line.splitn(3, ' ');
+        let status = parts.nth(1).and_then(|s| s.parse().ok())
+            .ok_or(FetchError::Network(format!("bad status line: {}", status_line)))?;
+        let text = parts.next().unwrap_or("").to_owned();
+        let headers = parse_headers(lines.next().unwrap_or(""));
+        if !(200..300).contains(&status) {
+            return Err(FetchError::Status(status, text));
+        }
+        Ok(Response { status, headers, body })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::rc::Rc;
+    use std::thread;
+
+    fn fetch_now(url: &str, timeout: Option<Duration>) -> Result<Response, FetchError> {
+        let result = Rc::new(RefCell::new(None));
+        let r = result.clone();
+        fetch_bytes_with_timeout(url, timeout, move |res| *r.borrow_mut() = Some(res));
+        let res = result.borrow_mut().take();
+        res.expect("native fetch calls back right away")
+    }
+
+    /// answer one request for each of `responses`
+    fn serve(responses: Vec<&'static [u8]>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for rsp in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                // read the whole request
+                let mut req = vec![];
+                let mut buf = [0; 256];
+                while !req.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => req.extend_from_slice(&buf[..n]),
+                    }
+                }
+                if rsp.is_empty() {
+                    // never answer
+                    thread::sleep(Duration::from_millis(500));
+                    continue;
+                }
+                stream.write_all(rsp).unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn local_files() {
+        let rsp = fetch_now("Cargo.toml", None).unwrap();
+        assert_eq!(rsp.status, 200);
+        assert!(String::from_utf8(rsp.body.clone()).unwrap().contains("sdl2-gallery"));
+        assert_eq!(rsp.header("Content-Length"), Some(rsp.body.len().to_string().as_str()));
+        assert_eq!(fetch_now("no/such/file.png", None), Err(FetchError::Status(404, "Not Found".to_owned())));
+    }
+
+    #[test]
+    fn http_status_headers_and_timeout() {
+        let base = serve(vec![
+            b"HTTP/1.0 200 OK\r\nContent-Type: image/png\r\nX-Test: yes\r\n\r\n\x89PNG",
+            b"HTTP/1.0 404 Not Found\r\n\r\n",
+            b"",
+        ]);
+        let rsp = fetch_now(&format!("{}/a.png", base), None).unwrap();
+        assert_eq!(rsp.status, 200);
+        assert_eq!(rsp.header("content-type"), Some("image/png"));
+        assert_eq!(rsp.header("x-test"), Some("yes"));
+        assert_eq!(rsp.body, b"\x89PNG".to_vec());
+
+        assert_eq!(fetch_now(&format!("{}/missing", base), None),
+                   Err(FetchError::Status(404, "Not Found".to_owned())));
+        assert_eq!(fetch_now(&format!("{}/slow", base), Some(Duration::from_millis(50))),
+                   Err(FetchError::Timeout));
+    }
+}
@@ -1,27 +1,31 @@
-use std::time::{Instant, Duration};
+use std::time::Duration;
 use std::collections::VecDeque;
+use std::rc::Rc;
 use utils::mean::Mean;
+use clock::Clock;
 
 pub struct FrameRate {
-    times: VecDeque<Instant>,
+    clock: Rc<Clock>,
+    times: VecDeque<Duration>,
     mean: Mean<u32>
 }
 
 impl FrameRate {
-    pub fn new() -> Self {
+    pub fn new(clock: Rc<Clock>) -> Self {
         FrameRate {
+            clock,
             times: VecDeque::with_capacity(100),
             mean: Mean::new(5)
         }
     }
 
     pub fn tick(&mut self) {
-        let now = Instant::now();
+        let now = self.clock.now();
         let duration = Duration::from_secs(1);
 
         self.times.push_back(now);
 
-        while self.times.front().is_some() && now.duration_since(*self.times.front().unwrap()) > duration {
+        while self.times.front().is_some() && now - *self.times.front().unwrap() > duration {
             self.times.pop_front();
         }
     }
@@ -40,17 +44,18 @@ impl FrameRate {
 
 #[cfg(test)]
 mod tests {
-    use std::thread::sleep;
     use super::*;
+    use clock::ManualClock;
 
     #[test]
     fn framerate_works() {
-        let mut rate = FrameRate::new(200);
+        let clock = Rc::new(ManualClock::new());
+        let mut rate = FrameRate::new(clock.clone());
         for _ in 0..10 {
             rate.tick();
         }
         assert!(rate.get() == 10);
-        sleep(Duration::new(1, 100_000_000));
+        clock.advance(Duration::new(1, 100_000_000));
         rate.tick();
         assert!(rate.get() == 1);
     }
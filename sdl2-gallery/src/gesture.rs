@@ -1,6 +1,8 @@
 use std::f32;
 use sdl2::event::Event;
-use sdl2::touch::num_touch_fingers;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
+#[cfg(test)]
+use sdl2::mouse::MouseState;
 
 pub enum GestureEvent {
     Tap(f32, f32),
@@ -29,41 +31,176 @@ pub enum GestureEvent {
         dy: f32,
         pressure: f32,
     },
+    PinchStart {
+        center: (f32, f32),
+    },
+    /// `scale` is relative to the previous pinch event,
+    /// so it can be applied to the current scale directly
+    Pinch {
+        center: (f32, f32),
+        scale: f32,
+    },
+    PinchEnd,
+    LongPress(f32, f32),
+    /// `velocity` is in screen ratio per second
+    Swipe {
+        direction: SwipeDirection,
+        velocity: f32,
+    },
+    /// mouse wheel at mouse position (x, y), positive `dy` scrolls up
+    Wheel {
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwipeDirection {
+    Left, Right, Up, Down,
+}
 
+#[derive(Clone, Copy)]
 pub enum GestureDetectorTypes {
-    Tap, Pan,
+    Tap, Pan, Pinch, LongPress, Swipe, Wheel,
+}
+
+/// thresholds used by the detectors,
+/// durations are in ms, distances are in screen ratio
+#[derive(Debug, Clone, Copy)]
+pub struct GestureConfig {
+    pub tap_duration: u32,
+    pub doubletap_duration: u32,
+    pub tap_dist: f32,
+    /// max move before a tap or long press is cancelled
+    pub tap_slop: f32,
+    pub long_press_duration: u32,
+    pub swipe_dist: f32,
+    pub swipe_velocity: f32,
+    /// screen size in px, used to convert mouse position to screen ratio,
+    /// mouse events are ignored if not set
+    pub screen_size: Option<(u32, u32)>,
 }
 
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            tap_duration: 150,
+            doubletap_duration: 300,
+            tap_dist: 0.04,
+            tap_slop: 0.02,
+            long_press_duration: 500,
+            swipe_dist: 0.1,
+            swipe_velocity: 0.5,
+            screen_size: None,
+        }
+    }
+}
+
+/// mouse events sdl synthesized from touch events, SDL_TOUCH_MOUSEID
+const TOUCH_MOUSE_ID: u32 = 0xFFFFFFFF;
+/// touch id of finger events made from mouse events, SDL_MOUSE_TOUCHID
+const MOUSE_TOUCH_ID: i64 = -1;
+
 pub struct GestureDetector {
     pool: Vec<GestureEvent>,
     detectors: Vec<Box<Detector>>,
+    types: Vec<GestureDetectorTypes>,
+    config: GestureConfig,
+    screen_size: Option<(u32, u32)>,
+    mouse_down: bool,
 }
 
 impl GestureDetector {
     pub fn new(types: Vec<GestureDetectorTypes>) -> GestureDetector {
+        GestureDetector::with_config(types, GestureConfig::default())
+    }
+    pub fn with_config(types: Vec<GestureDetectorTypes>, config: GestureConfig) -> GestureDetector {
         GestureDetector {
             pool: vec![],
+            screen_size: config.screen_size,
+            mouse_down: false,
+            config,
             detectors: types.iter().map(|t| {
                 match t {
                     &GestureDetectorTypes::Tap => {
-                        Box::new(TapDetector::new()) as Box<Detector>
+                        Box::new(TapDetector::new(config)) as Box<Detector>
                     },
                     &GestureDetectorTypes::Pan => {
                         Box::new(PanDetector::new()) as Box<Detector>
                     },
+                    &GestureDetectorTypes::Pinch => {
+                        Box::new(PinchDetector::new()) as Box<Detector>
+                    },
+                    &GestureDetectorTypes::LongPress => {
+                        Box::new(LongPressDetector::new(config)) as Box<Detector>
+                    },
+                    &GestureDetectorTypes::Swipe => {
+                        Box::new(SwipeDetector::new(config)) as Box<Detector>
+                    },
+                    &GestureDetectorTypes::Wheel => {
+                        Box::new(WheelDetector::new(config)) as Box<Detector>
+                    },
                 }
-            }).collect()
+            }).collect(),
+            types,
         }
     }
+    /// after the window resized, gestures in progress are dropped
+    pub fn set_screen_size(&mut self, size: (u32, u32)) {
+        let config = GestureConfig { screen_size: Some(size), ..self.config };
+        *self = GestureDetector::with_config(self.types.clone(), config);
+    }
     pub fn feed(&mut self, evt: &Event) {
+        // left mouse button drives detectors as a finger
+        let finger = self.mouse_to_finger(evt);
+        let evt = finger.as_ref().unwrap_or(evt);
         for d in &mut self.detectors {
             if let Some(g) = d.feed(evt) {
                 self.pool.push(g);
             }
         }
     }
+    fn mouse_to_finger(&mut self, evt: &Event) -> Option<Event> {
+        let (w, h) = match self.screen_size {
+            Some((w, h)) => (w as f32, h as f32),
+            None => return None,
+        };
+        match evt {
+            &Event::MouseButtonDown { timestamp, which, mouse_btn: MouseButton::Left, x, y, .. } if which != TOUCH_MOUSE_ID => {
+                self.mouse_down = true;
+                Some(Event::FingerDown {
+                    timestamp, touch_id: MOUSE_TOUCH_ID, finger_id: 0,
+                    x: x as f32 / w, y: y as f32 / h, dx: 0., dy: 0., pressure: 1.,
+                })
+            },
+            &Event::MouseMotion { timestamp, which, x, y, xrel, yrel, .. } if which != TOUCH_MOUSE_ID && self.mouse_down => {
+                Some(Event::FingerMotion {
+                    timestamp, touch_id: MOUSE_TOUCH_ID, finger_id: 0,
+                    x: x as f32 / w, y: y as f32 / h,
+                    dx: xrel as f32 / w, dy: yrel as f32 / h, pressure: 1.,
+                })
+            },
+            &Event::MouseButtonUp { timestamp, which, mouse_btn: MouseButton::Left, x, y, .. } if which != TOUCH_MOUSE_ID && self.mouse_down => {
+                self.mouse_down = false;
+                Some(Event::FingerUp {
+                    timestamp, touch_id: MOUSE_TOUCH_ID, finger_id: 0,
+                    x: x as f32 / w, y: y as f32 / h, dx: 0., dy: 0., pressure: 0.,
+                })
+            },
+            _ => None,
+        }
+    }
+    /// let time based detectors fire without a new event,
+    /// `timestamp` is in ms, same as sdl event timestamps
+    pub fn update(&mut self, timestamp: u32) {
+        for d in &mut self.detectors {
+            if let Some(g) = d.update(timestamp) {
+                self.pool.push(g);
+            }
+        }
+    }
     pub fn poll(&mut self) -> Vec<GestureEvent> {
         self.pool.drain(0..).collect()
     }
@@ -71,20 +208,19 @@ impl GestureDetector {
 
 trait Detector {
     fn feed(&mut self, evt: &Event) -> Option<GestureEvent>;
+    fn update(&mut self, _timestamp: u32) -> Option<GestureEvent> { None }
 }
 
-const TAP_DURATION: u32 = 150;
-const DOUBLETAP_DURATION: u32 = 300;
-const TAP_DIST: f32 = 0.04;
-
 pub struct TapDetector {
+    config: GestureConfig,
     prev_finger_down: Option<Event>,
     prev_tap: Option<(f32, f32, u32)>,
 }
 
 impl TapDetector {
-    fn new() -> TapDetector {
+    fn new(config: GestureConfig) -> TapDetector {
         TapDetector {
+            config,
             prev_finger_down: None,
             prev_tap: None,
         }
@@ -111,7 +247,7 @@ impl Detector for TapDetector {
                 let mut cancel = false;
                 if let Some(ref down) = self.prev_finger_down {
                     if let &Event::FingerDown {finger_id: finger_id0, x: x0, y: y0, ..} = down {
-                        if finger_id0 == finger_id && get_dist(x, y, x0, y0) >= 0.02 {
+                        if finger_id0 == finger_id && get_dist(x, y, x0, y0) >= self.config.tap_slop {
                             // moved too far, cancel the tap
                             cancel = true;
                         }
@@ -126,11 +262,11 @@ impl Detector for TapDetector {
                 if let Some(ref down) = self.prev_finger_down {
                     if let &Event::FingerDown {x: x0, y: y0, touch_id: touch_id0, timestamp: timestamp0, finger_id: finger_id0, ..} = down {
                         if finger_id == finger_id0 {
-                            if get_dist(x, y, x0, y0) < TAP_DIST && timestamp - timestamp0 < TAP_DURATION {
+                            if get_dist(x, y, x0, y0) < self.config.tap_dist && timestamp.saturating_sub(timestamp0) < self.config.tap_duration {
                                 // we got a Tap or a DoubleTap
                                 let mut single = true;
                                 if let Some(ref tap) = self.prev_tap {
-                                    if get_dist(x, y, tap.0, tap.1) < TAP_DIST && timestamp - tap.2 < DOUBLETAP_DURATION {
+                                    if get_dist(x, y, tap.0, tap.1) < self.config.tap_dist && timestamp.saturating_sub(tap.2) < self.config.doubletap_duration {
                                         single = false;
                                     }
                                 }
@@ -151,15 +287,16 @@ impl Detector for TapDetector {
 }
 
 pub struct PanDetector {
-    curr_touch_id: i64,
-    curr_finger_id: i64,
+    /// (touch_id, finger_id) of the panning finger and its position
+    curr: Option<((i64, i64), f32, f32)>,
+    fingers: Vec<(i64, i64)>, // fingers currently down
 }
 
 impl PanDetector {
     fn new() -> PanDetector {
         PanDetector {
-            curr_touch_id: -1,
-            curr_finger_id: -1,
+            curr: None,
+            fingers: vec![],
         }
     }
 }
@@ -167,19 +304,152 @@ impl Detector for PanDetector {
     fn feed(&mut self, evt: &Event) -> Option<GestureEvent> {
         match evt {
             &Event::FingerDown { x, y, dx, dy, touch_id, finger_id, timestamp, pressure } => {
-                self.curr_touch_id = touch_id;
-                self.curr_finger_id = finger_id;
-                return Some(GestureEvent::PanStart{x, y, dx, dy, timestamp, pressure});
+                let key = (touch_id, finger_id);
+                if !self.fingers.contains(&key) {
+                    self.fingers.push(key);
+                }
+                // only the first finger starts a pan
+                if self.fingers.len() == 1 {
+                    self.curr = Some((key, x, y));
+                    return Some(GestureEvent::PanStart{x, y, dx, dy, timestamp, pressure});
+                }
+                // multi finger move is not a pan, end it where the finger is
+                if let Some((_, x, y)) = self.curr.take() {
+                    return Some(GestureEvent::PanEnd{x, y, dx: 0., dy: 0., timestamp, pressure});
+                }
             },
             &Event::FingerMotion { x, y, dx, dy, touch_id, finger_id, timestamp, pressure } => {
-                let fingers = num_touch_fingers(1);
-                if fingers == 1 && self.curr_touch_id == touch_id && self.curr_finger_id == finger_id {
-                    return Some(GestureEvent::Pan{x, y, dx, dy, timestamp, pressure});
+                if let Some((key, ..)) = self.curr {
+                    if key == (touch_id, finger_id) {
+                        self.curr = Some((key, x, y));
+                        return Some(GestureEvent::Pan{x, y, dx, dy, timestamp, pressure});
+                    }
                 }
             },
             &Event::FingerUp { x, y, dx, dy, touch_id, finger_id, timestamp, pressure, .. } => {
-                if self.curr_touch_id == touch_id && self.curr_finger_id == finger_id {
-                    return Some(GestureEvent::PanEnd{x, y, dx, dy, timestamp, pressure});
+                let key = (touch_id, finger_id);
+                self.fingers.retain(|&f| f != key);
+                if let Some((k, ..)) = self.curr {
+                    if k == key {
+                        self.curr = None;
+                        return Some(GestureEvent::PanEnd{x, y, dx, dy, timestamp, pressure});
+                    }
+                }
+            },
+            _ => ()
+        }
+        None
+    }
+}
+
+/// factor to turn MultiGesture's d_dist into a scale
+const PINCH_GESTURE_FACTOR: f32 = 5.;
+
+/// track the first two fingers down, and report the change of distance between them
+pub struct PinchDetector {
+    /// (touch_id, finger_id) and position of the fingers down, in the order they went down
+    fingers: Vec<((i64, i64), (f32, f32))>,
+    pinching: bool,
+    prev_dist: f32,
+    /// pinching from `MultiGesture` alone, on platforms that do not report every finger
+    gesture_pinching: bool,
+}
+
+impl PinchDetector {
+    fn new() -> PinchDetector {
+        PinchDetector {
+            fingers: vec![],
+            pinching: false,
+            prev_dist: 0.,
+            gesture_pinching: false,
+        }
+    }
+    /// the two fingers pinching
+    fn tracked(&self) -> Vec<(i64, i64)> {
+        self.fingers.iter().take(2).map(|f| f.0).collect()
+    }
+    /// center and distance of the two tracked fingers
+    fn measure(&self) -> Option<((f32, f32), f32)> {
+        if self.fingers.len() < 2 {
+            return None;
+        }
+        let (x0, y0) = self.fingers[0].1;
+        let (x1, y1) = self.fingers[1].1;
+        Some((((x0 + x1) / 2., (y0 + y1) / 2.), get_dist(x0, y0, x1, y1)))
+    }
+    /// start over after the tracked fingers changed, or end if fewer than two are left
+    fn restart(&mut self) -> Option<GestureEvent> {
+        match self.measure() {
+            Some((center, dist)) => {
+                self.pinching = true;
+                self.prev_dist = dist;
+                Some(GestureEvent::PinchStart{center})
+            },
+            None if self.pinching => {
+                self.pinching = false;
+                Some(GestureEvent::PinchEnd)
+            },
+            None => None,
+        }
+    }
+}
+
+impl Detector for PinchDetector {
+    fn feed(&mut self, evt: &Event) -> Option<GestureEvent> {
+        match evt {
+            &Event::FingerDown { x, y, touch_id, finger_id, .. } => {
+                let key = (touch_id, finger_id);
+                let tracked = self.tracked();
+                self.fingers.retain(|f| f.0 != key);
+                self.fingers.push((key, (x, y)));
+                if self.tracked() != tracked {
+                    return self.restart();
+                }
+            },
+            &Event::FingerMotion { x, y, touch_id, finger_id, .. } => {
+                let key = (touch_id, finger_id);
+                match self.fingers.iter_mut().find(|f| f.0 == key) {
+                    Some(f) => f.1 = (x, y),
+                    None => return None,
+                }
+                if self.pinching && self.tracked().contains(&key) {
+                    if let Some((center, dist)) = self.measure() {
+                        if self.prev_dist <= 0. || dist <= 0. {
+                            return None;
+                        }
+                        let scale = dist / self.prev_dist;
+                        self.prev_dist = dist;
+                        return Some(GestureEvent::Pinch{center, scale});
+                    }
+                }
+            },
+            &Event::FingerUp { touch_id, finger_id, .. } => {
+                if self.gesture_pinching {
+                    self.gesture_pinching = false;
+                    return Some(GestureEvent::PinchEnd);
+                }
+                let key = (touch_id, finger_id);
+                let tracked = self.tracked();
+                self.fingers.retain(|f| f.0 != key);
+                if self.tracked() != tracked {
+                    return self.restart();
+                }
+            },
+            &Event::MultiGesture { x, y, d_dist, num_fingers, .. } => {
+                if self.pinching {
+                    // fingers are reported one by one
+                    return None;
+                }
+                if num_fingers == 2 {
+                    if !self.gesture_pinching {
+                        self.gesture_pinching = true;
+                        return Some(GestureEvent::PinchStart{center: (x, y)});
+                    }
+                    let scale = 1. + d_dist * PINCH_GESTURE_FACTOR;
+                    return Some(GestureEvent::Pinch{center: (x, y), scale});
+                } else if self.gesture_pinching {
+                    self.gesture_pinching = false;
+                    return Some(GestureEvent::PinchEnd);
                 }
             },
             _ => ()
@@ -188,6 +458,450 @@ impl Detector for PanDetector {
     }
 }
 
+/// a finger held still for `long_press_duration`
+pub struct LongPressDetector {
+    config: GestureConfig,
+    down: Option<(i64, f32, f32, u32)>, // finger_id, x, y, timestamp
+}
+
+impl LongPressDetector {
+    fn new(config: GestureConfig) -> LongPressDetector {
+        LongPressDetector {
+            config,
+            down: None,
+        }
+    }
+    /// fire once the press is held long enough, the press is consumed after
+    fn check(&mut self, timestamp: u32) -> Option<GestureEvent> {
+        if let Some((_, x, y, timestamp0)) = self.down {
+            if timestamp.saturating_sub(timestamp0) >= self.config.long_press_duration {
+                self.down = None;
+                return Some(GestureEvent::LongPress(x, y));
+            }
+        }
+        None
+    }
+}
+
+impl Detector for LongPressDetector {
+    fn feed(&mut self, evt: &Event) -> Option<GestureEvent> {
+        match evt {
+            &Event::FingerDown { x, y, finger_id, timestamp, .. } => {
+                if self.down.is_some() {
+                    // second finger, not a long press
+                    self.down = None;
+                } else {
+                    self.down = Some((finger_id, x, y, timestamp));
+                }
+            },
+            &Event::FingerMotion { x, y, finger_id, timestamp, .. } => {
+                if let Some((finger_id0, x0, y0, _)) = self.down {
+                    if finger_id == finger_id0 {
+                        if get_dist(x, y, x0, y0) >= self.config.tap_slop {
+                            // moved too far, cancel the press
+                            self.down = None;
+                        } else {
+                            return self.check(timestamp);
+                        }
+                    }
+                }
+            },
+            &Event::FingerUp { finger_id, timestamp, .. } => {
+                if let Some((finger_id0, ..)) = self.down {
+                    if finger_id == finger_id0 {
+                        let g = self.check(timestamp);
+                        self.down = None;
+                        return g;
+                    }
+                }
+            },
+            _ => ()
+        }
+        None
+    }
+    fn update(&mut self, timestamp: u32) -> Option<GestureEvent> {
+        self.check(timestamp)
+    }
+}
+
+/// a quick single finger fling
+pub struct SwipeDetector {
+    config: GestureConfig,
+    down: Option<(i64, f32, f32, u32)>, // finger_id, x, y, timestamp
+}
+
+impl SwipeDetector {
+    fn new(config: GestureConfig) -> SwipeDetector {
+        SwipeDetector {
+            config,
+            down: None,
+        }
+    }
+}
+
+impl Detector for SwipeDetector {
+    fn feed(&mut self, evt: &Event) -> Option<GestureEvent> {
+        match evt {
+            &Event::FingerDown { x, y, finger_id, timestamp, .. } => {
+                if self.down.is_some() {
+                    // multi finger move is not a swipe
+                    self.down = None;
+                } else {
+                    self.down = Some((finger_id, x, y, timestamp));
+                }
+            },
+            &Event::FingerUp { x, y, finger_id, timestamp, .. } => {
+                if let Some((finger_id0, x0, y0, timestamp0)) = self.down {
+                    if finger_id != finger_id0 {
+                        return None;
+                    }
+                    self.down = None;
+
+                    let dist = get_dist(x, y, x0, y0);
+                    // at least 1ms to avoid dividing by zero
+                    let duration = timestamp.saturating_sub(timestamp0).max(1) as f32 / 1000.;
+                    let velocity = dist / duration;
+                    if dist < self.config.swipe_dist || velocity < self.config.swipe_velocity {
+                        return None;
+                    }
+
+                    let (dx, dy) = (x - x0, y - y0);
+                    let direction = if dx.abs() > dy.abs() {
+                        if dx > 0. { SwipeDirection::Right } else { SwipeDirection::Left }
+                    } else {
+                        if dy > 0. { SwipeDirection::Down } else { SwipeDirection::Up }
+                    };
+                    return Some(GestureEvent::Swipe{direction, velocity});
+                }
+            },
+            _ => ()
+        }
+        None
+    }
+}
+
+/// report mouse wheel with the mouse position
+pub struct WheelDetector {
+    screen_size: Option<(u32, u32)>,
+    pos: (f32, f32),
+}
+
+impl WheelDetector {
+    fn new(config: GestureConfig) -> WheelDetector {
+        WheelDetector {
+            screen_size: config.screen_size,
+            pos: (0.5, 0.5),
+        }
+    }
+}
+
+impl Detector for WheelDetector {
+    fn feed(&mut self, evt: &Event) -> Option<GestureEvent> {
+        let (w, h) = match self.screen_size {
+            Some((w, h)) => (w as f32, h as f32),
+            None => return None,
+        };
+        match evt {
+            &Event::MouseMotion { x, y, .. } => {
+                self.pos = (x as f32 / w, y as f32 / h);
+            },
+            &Event::MouseWheel { x, y, ref direction, .. } => {
+                let sign = match direction {
+                    &MouseWheelDirection::Flipped => -1.,
+                    _ => 1.,
+                };
+                let (px, py) = self.pos;
+                return Some(GestureEvent::Wheel{x: px, y: py, dx: sign * x as f32, dy: sign * y as f32});
+            },
+            _ => ()
+        }
+        None
+    }
+}
+
 fn get_dist(x: f32, y: f32, x0: f32, y0: f32) -> f32 {
     ((x - x0).powi(2) + (y - y0).powi(2)).sqrt()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(finger_id: i64, x: f32, y: f32, timestamp: u32) -> Event {
+        Event::FingerDown { timestamp, touch_id: 0, finger_id, x, y, dx: 0., dy: 0., pressure: 1. }
+    }
+
+    fn motion(finger_id: i64, x: f32, y: f32, timestamp: u32) -> Event {
+        Event::FingerMotion { timestamp, touch_id: 0, finger_id, x, y, dx: 0., dy: 0., pressure: 1. }
+    }
+
+    fn up(finger_id: i64, x: f32, y: f32, timestamp: u32) -> Event {
+        Event::FingerUp { timestamp, touch_id: 0, finger_id, x, y, dx: 0., dy: 0., pressure: 1. }
+    }
+
+    fn mouse(down: bool, x: i32, y: i32, timestamp: u32) -> Event {
+        if down {
+            Event::MouseButtonDown { timestamp, window_id: 0, which: 0, mouse_btn: MouseButton::Left, clicks: 1, x, y }
+        } else {
+            Event::MouseButtonUp { timestamp, window_id: 0, which: 0, mouse_btn: MouseButton::Left, clicks: 1, x, y }
+        }
+    }
+
+    fn run(types: Vec<GestureDetectorTypes>, events: Vec<Event>) -> Vec<GestureEvent> {
+        let mut detector = GestureDetector::new(types);
+        for e in &events {
+            detector.feed(e);
+        }
+        detector.poll()
+    }
+
+    #[test]
+    fn tap_and_double_tap() {
+        let gestures = run(vec![GestureDetectorTypes::Tap], vec![
+            down(1, 0.5, 0.5, 0),
+            up(1, 0.5, 0.5, 50),
+            down(1, 0.5, 0.5, 100),
+            up(1, 0.5, 0.5, 150),
+        ]);
+        assert_eq!(gestures.len(), 2);
+        match gestures[0] { GestureEvent::Tap(..) => (), _ => panic!("expect tap") }
+        match gestures[1] { GestureEvent::DoubleTap(..) => (), _ => panic!("expect double tap") }
+    }
+
+    #[test]
+    fn tap_timestamps_out_of_order() {
+        // timestamps going backwards must not overflow
+        let gestures = run(vec![GestureDetectorTypes::Tap], vec![
+            down(1, 0.5, 0.5, 100),
+            up(1, 0.5, 0.5, 50),
+            down(1, 0.5, 0.5, 40),
+            up(1, 0.5, 0.5, 30),
+        ]);
+        assert_eq!(gestures.len(), 2);
+    }
+
+    #[test]
+    fn long_press_on_release() {
+        let gestures = run(vec![GestureDetectorTypes::LongPress, GestureDetectorTypes::Tap], vec![
+            down(1, 0.5, 0.5, 0),
+            up(1, 0.5, 0.5, 600),
+        ]);
+        assert_eq!(gestures.len(), 1);
+        match gestures[0] { GestureEvent::LongPress(x, y) => assert_eq!((x, y), (0.5, 0.5)), _ => panic!("expect long press") }
+    }
+
+    #[test]
+    fn long_press_on_update() {
+        let mut detector = GestureDetector::new(vec![GestureDetectorTypes::LongPress]);
+        detector.feed(&down(1, 0.5, 0.5, 0));
+        detector.update(100);
+        assert!(detector.poll().is_empty());
+        detector.update(500);
+        assert_eq!(detector.poll().len(), 1);
+        // fires only once
+        detector.feed(&up(1, 0.5, 0.5, 700));
+        assert!(detector.poll().is_empty());
+    }
+
+    #[test]
+    fn long_press_cancelled_by_move() {
+        let gestures = run(vec![GestureDetectorTypes::LongPress], vec![
+            down(1, 0.5, 0.5, 0),
+            motion(1, 0.6, 0.5, 100),
+            up(1, 0.6, 0.5, 600),
+        ]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn long_press_configurable() {
+        let config = GestureConfig { long_press_duration: 1000, ..Default::default() };
+        let mut detector = GestureDetector::with_config(vec![GestureDetectorTypes::LongPress], config);
+        detector.feed(&down(1, 0.5, 0.5, 0));
+        detector.feed(&up(1, 0.5, 0.5, 600));
+        assert!(detector.poll().is_empty());
+    }
+
+    #[test]
+    fn swipe_direction_and_velocity() {
+        let gestures = run(vec![GestureDetectorTypes::Swipe], vec![
+            down(1, 0.8, 0.5, 0),
+            motion(1, 0.5, 0.5, 100),
+            up(1, 0.2, 0.55, 200),
+        ]);
+        assert_eq!(gestures.len(), 1);
+        match gestures[0] {
+            GestureEvent::Swipe { direction, velocity } => {
+                assert_eq!(direction, SwipeDirection::Left);
+                assert!(velocity > 2.9 && velocity < 3.1);
+            },
+            _ => panic!("expect swipe"),
+        }
+
+        let gestures = run(vec![GestureDetectorTypes::Swipe], vec![
+            down(1, 0.5, 0.2, 0),
+            up(1, 0.5, 0.6, 100),
+        ]);
+        match gestures[0] {
+            GestureEvent::Swipe { direction, .. } => assert_eq!(direction, SwipeDirection::Down),
+            _ => panic!("expect swipe"),
+        }
+    }
+
+    #[test]
+    fn slow_or_short_move_is_not_swipe() {
+        // too slow
+        let gestures = run(vec![GestureDetectorTypes::Swipe], vec![
+            down(1, 0.8, 0.5, 0),
+            up(1, 0.2, 0.5, 2000),
+        ]);
+        assert!(gestures.is_empty());
+
+        // too short
+        let gestures = run(vec![GestureDetectorTypes::Swipe], vec![
+            down(1, 0.5, 0.5, 0),
+            up(1, 0.45, 0.5, 10),
+        ]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn two_finger_move_is_not_swipe() {
+        let gestures = run(vec![GestureDetectorTypes::Swipe], vec![
+            down(1, 0.8, 0.5, 0),
+            down(2, 0.8, 0.6, 10),
+            up(1, 0.2, 0.5, 100),
+            up(2, 0.2, 0.6, 100),
+        ]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn pinch() {
+        let gestures = run(vec![GestureDetectorTypes::Pinch, GestureDetectorTypes::Pan], vec![
+            down(1, 0.4, 0.5, 0),
+            down(2, 0.6, 0.5, 10),
+            motion(2, 0.8, 0.5, 20),
+            up(2, 0.8, 0.5, 30),
+        ]);
+        let pinches: Vec<f32> = gestures.iter().filter_map(|g| {
+            match g {
+                &GestureEvent::Pinch { scale, .. } => Some(scale),
+                _ => None,
+            }
+        }).collect();
+        assert_eq!(pinches.len(), 1);
+        assert!((pinches[0] - 2.).abs() < 0.0001);
+        // two finger move does not pan
+        assert!(!gestures.iter().any(|g| match g { &GestureEvent::Pan { .. } => true, _ => false }));
+        match gestures.last() { Some(&GestureEvent::PinchEnd) => (), _ => panic!("expect pinch end") }
+    }
+
+    fn pinch_events(gestures: &[GestureEvent]) -> Vec<String> {
+        gestures.iter().filter_map(|g| match g {
+            &GestureEvent::PinchStart { .. } => Some("start".to_owned()),
+            &GestureEvent::Pinch { scale, .. } => Some(format!("{:.2}", scale)),
+            &GestureEvent::PinchEnd => Some("end".to_owned()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn pinch_restarts_when_fingers_change() {
+        let gestures = run(vec![GestureDetectorTypes::Pinch], vec![
+            down(1, 0.4, 0.5, 0),
+            down(2, 0.6, 0.5, 10),
+            // a third finger is not tracked
+            down(3, 0.9, 0.5, 20),
+            motion(3, 1., 0.5, 30),
+            motion(3, 0.9, 0.5, 35),
+            motion(2, 0.8, 0.5, 40),
+            // 2 and 3 pinch now, 0.1 apart
+            up(1, 0.4, 0.5, 50),
+            motion(3, 1.1, 0.5, 60),
+            up(2, 0.8, 0.5, 70),
+            up(3, 1.1, 0.5, 80),
+        ]);
+        assert_eq!(pinch_events(&gestures), vec!["start", "2.00", "start", "3.00", "end"]);
+    }
+
+    #[test]
+    fn mouse_and_touch_fingers_pinch() {
+        let config = GestureConfig { screen_size: Some((100, 100)), ..Default::default() };
+        let mut detector = GestureDetector::with_config(vec![GestureDetectorTypes::Pinch], config);
+        // touch finger 0 and the mouse finger, also id 0
+        detector.feed(&down(0, 0.4, 0.5, 0));
+        detector.feed(&mouse(true, 60, 50, 10));
+        detector.feed(&Event::MouseMotion {
+            timestamp: 20, window_id: 0, which: 0, mousestate: MouseState::from_sdl_state(1),
+            x: 80, y: 50, xrel: 20, yrel: 0,
+        });
+        assert_eq!(pinch_events(&detector.poll()), vec!["start", "2.00"]);
+    }
+
+    #[test]
+    fn pinch_from_multi_gesture() {
+        let gesture = |d_dist: f32, num_fingers: u16| Event::MultiGesture {
+            timestamp: 0, touch_id: 0, d_theta: 0., d_dist, x: 0.5, y: 0.5, num_fingers,
+        };
+        let gestures = run(vec![GestureDetectorTypes::Pinch], vec![
+            gesture(0., 2),
+            gesture(0.1, 2),
+            gesture(-0.1, 2),
+            gesture(0., 3),
+            // not pinching
+            gesture(0., 3),
+        ]);
+        assert_eq!(pinch_events(&gestures), vec!["start", "1.50", "0.50", "end"]);
+    }
+
+    #[test]
+    fn pan_ends_when_second_finger_lands() {
+        let gestures = run(vec![GestureDetectorTypes::Pan], vec![
+            down(1, 0.4, 0.5, 0),
+            motion(1, 0.3, 0.5, 10),
+            down(2, 0.6, 0.5, 20),
+            motion(1, 0.2, 0.5, 30),
+            up(1, 0.2, 0.5, 40),
+            up(2, 0.6, 0.5, 50),
+        ]);
+        let names: Vec<&str> = gestures.iter().map(|g| match g {
+            &GestureEvent::PanStart { .. } => "start",
+            &GestureEvent::Pan { .. } => "pan",
+            &GestureEvent::PanEnd { .. } => "end",
+            _ => "other",
+        }).collect();
+        assert_eq!(names, vec!["start", "pan", "end"]);
+        match gestures[2] {
+            GestureEvent::PanEnd { x, timestamp, .. } => assert_eq!((x, timestamp), (0.3, 20)),
+            _ => panic!("expect pan end"),
+        }
+    }
+
+    #[test]
+    fn mouse_as_finger() {
+        let config = GestureConfig { screen_size: Some((100, 100)), ..Default::default() };
+        let mut detector = GestureDetector::with_config(vec![GestureDetectorTypes::Tap, GestureDetectorTypes::Pan], config);
+        detector.feed(&mouse(true, 50, 50, 0));
+        detector.feed(&mouse(false, 50, 50, 50));
+        let gestures = detector.poll();
+        match gestures[0] { GestureEvent::PanStart { x, .. } => assert_eq!(x, 0.5), _ => panic!("expect pan start") }
+        match gestures[1] { GestureEvent::Tap(..) => (), _ => panic!("expect tap") }
+        match gestures[2] { GestureEvent::PanEnd { .. } => (), _ => panic!("expect pan end") }
+
+        // mouse is ignored without screen size
+        let gestures = run(vec![GestureDetectorTypes::Tap], vec![mouse(true, 50, 50, 0), mouse(false, 50, 50, 50)]);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn wheel() {
+        let config = GestureConfig { screen_size: Some((100, 100)), ..Default::default() };
+        let mut detector = GestureDetector::with_config(vec![GestureDetectorTypes::Wheel], config);
+        detector.feed(&Event::MouseWheel { timestamp: 0, window_id: 0, which: 0, x: 0, y: 1, direction: MouseWheelDirection::Flipped });
+        match detector.poll()[0] {
+            GestureEvent::Wheel { x, y, dy, .. } => assert_eq!((x, y, dy), (0.5, 0.5, -1.)),
+            _ => panic!("expect wheel"),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Browser session history, entries are paths kept in the url hash, e.g. `#/photo/12`.
+// Paths the browser goes back or forward to are queued until `poll`.
+// On pc the history is kept in memory and behaves like the browser's.
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// cheap handle, clones share the history
+#[derive(Clone)]
+pub struct History {
+    popped: Rc<RefCell<VecDeque<String>>>,
+    #[cfg(not(target_os = "emscripten"))]
+    entries: Rc<RefCell<(Vec<String>, usize)>>,
+}
+
+/// the hash without `#`, "/" if there is none
+fn normalize(hash: &str) -> String {
+    let path = hash.trim_start_matches('#');
+    if path.is_empty() {
+        "/".to_owned()
+    } else {
+        path.to_owned()
+    }
+}
+
+#[cfg(target_os = "emscripten")]
+impl History {
+    pub fn new() -> History {
+        let popped = Rc::new(RefCell::new(VecDeque::new()));
+        let p = popped.clone();
+        let on_pop = move |hash: String| p.borrow_mut().push_back(normalize(&hash));
+        js! { @(no_return)
+            var on_pop = @{on_pop};
+            window.addEventListener("popstate", function () {
+                on_pop(location.hash);
+            });
+        }
+        History { popped }
+    }
+
+    /// path of the current entry
+    pub fn path(&self) -> String {
+        use stdweb::unstable::TryInto;
+        let hash: String = js! { return location.hash; }.try_into().unwrap_or_default();
+        normalize(&hash)
+    }
+
+    /// add an entry after the current one, dropping the forward ones
+    pub fn push(&self, path: &str) {
+        js! { @(no_return)
+            history.pushState(null, "", "#" + @{path});
+        }
+    }
+
+    pub fn replace(&self, path: &str) {
+        js! { @(no_return)
+            history.replaceState(null, "", "#" + @{path});
+        }
+    }
+
+    /// move by `delta` entries, like the back and forward buttons
+    pub fn go(&self, delta: i32) {
+        js! { @(no_return)
+            history.go(@{delta});
+        }
+    }
+}
+
+#[cfg(not(target_os = "emscripten"))]
+impl History {
+    pub fn new() -> History {
+        History {
+            popped: Rc::new(RefCell::new(VecDeque::new())),
+            entries: Rc::new(RefCell::new((vec!["/".to_owned()], 0))),
+        }
+    }
+
+    /// path of the current entry
+    pub fn path(&self) -> String {
+        let e = self.entries.borrow();
+        e.0[e.1].clone()
+    }
+
+    /// add an entry after the current one, dropping the forward ones
+    pub fn push(&self, path: &str) {
+        let mut e = self.entries.borrow_mut();
+        let i = e.1 + 1;
+        e.0.truncate(i);
+        e.0.push(normalize(path));
+        e.1 = i;
+    }
+
+    pub fn replace(&self, path: &str) {
+        let mut e = self.entries.borrow_mut();
+        let i = e.1;
+        e.0[i] = normalize(path);
+    }
+
+    /// move by `delta` entries, like the back and forward buttons
+    pub fn go(&self, delta: i32) {
+        let mut e = self.entries.borrow_mut();
+        let i = e.1 as i32 + delta;
+        if delta != 0 && i >= 0 && (i as usize) < e.0.len() {
+            e.1 = i as usize;
+            self.popped.borrow_mut().push_back(e.0[e.1].clone());
+        }
+    }
+}
+
+impl History {
+    pub fn back(&self) {
+        self.go(-1);
+    }
+
+    /// next path the browser went back or forward to
+    pub fn poll(&self) -> Option<String> {
+        self.popped.borrow_mut().pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_and_forward() {
+        let h = History::new();
+        assert_eq!(h.path(), "/");
+        h.push("/photo/1");
+        h.push("#/photo/2");
+        h.replace("/photo/3");
+        assert_eq!(h.path(), "/photo/3");
+        assert_eq!(h.poll(), None);
+
+        h.back();
+        h.back();
+        // nothing before the first entry
+        h.back();
+        assert_eq!(h.poll(), Some("/photo/1".to_owned()));
+        assert_eq!(h.poll(), Some("/".to_owned()));
+        assert_eq!(h.poll(), None);
+
+        h.go(2);
+        assert_eq!(h.poll(), Some("/photo/3".to_owned()));
+        // pushing drops the forward entries
+        h.go(-1);
+        h.push("/photo/4");
+        h.go(1);
+        assert_eq!(h.path(), "/photo/4");
+        assert_eq!(h.poll(), Some("/photo/1".to_owned()));
+        assert_eq!(h.poll(), None);
+    }
+}
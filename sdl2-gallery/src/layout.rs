@@ -0,0 +1,389 @@
+// Rects for the parts of a display.
+// A tree of nodes splits a rect: rows and columns share it along one axis,
+// grids cut it into equal cells and stacks give each child all of it.
+// A node takes its size from the space it is given, less its margin, and
+// is placed there by its anchor. Children go inside its padding.
+// Named nodes are looked up in the result, run it again when the window resizes.
+use std::cmp;
+use std::collections::HashMap;
+use sdl2::rect::{Rect, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Edges {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl Edges {
+    pub fn new(top: u32, right: u32, bottom: u32, left: u32) -> Edges {
+        Edges { top, right, bottom, left }
+    }
+    pub fn all(n: u32) -> Edges {
+        Edges::new(n, n, n, n)
+    }
+    /// `rect` less the edges, rects are at least 1px wide
+    pub fn shrink(&self, rect: Rect) -> Rect {
+        Rect::new(rect.x() + self.left as i32,
+                  rect.y() + self.top as i32,
+                  rect.width().saturating_sub(self.left + self.right),
+                  rect.height().saturating_sub(self.top + self.bottom))
+    }
+    fn horizontal(&self) -> u32 {
+        self.left + self.right
+    }
+    fn vertical(&self) -> u32 {
+        self.top + self.bottom
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    /// px, less if there is no room
+    Fixed(u32),
+    /// in rows and columns a share of the room fixed siblings leave, by weight,
+    /// elsewhere all of it
+    Fill(u32),
+}
+
+/// where a node smaller than its slot is placed
+// the views only anchor to the top and center so far
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// offset of a node in a slot with `free` px to spare
+    fn offset(&self, free: (u32, u32)) -> (i32, i32) {
+        let (w, h) = (free.0 as i32, free.1 as i32);
+        let x = match *self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => w / 2,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => w,
+        };
+        let y = match *self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0,
+            Anchor::Left | Anchor::Center | Anchor::Right => h / 2,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => h,
+        };
+        (x, y)
+    }
+}
+
+/// equal cells in rows, left to right then top to bottom
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub origin: Point,
+    pub columns: u32,
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub gap: u32,
+}
+
+impl Grid {
+    /// as many columns of at least `min_width` as fit in `area`,
+    /// cells are widened to fill it and keep the `aspect` of width to height
+    pub fn fit(area: Rect, min_width: u32, aspect: (u32, u32), gap: u32) -> Grid {
+        let columns = cmp::max(1, (area.width() + gap) / (min_width + gap));
+        let cell_width = (area.width() - gap * (columns - 1)) / columns;
+        Grid {
+            origin: area.top_left(),
+            columns,
+            cell_width,
+            cell_height: cell_width * aspect.1 / aspect.0,
+            gap,
+        }
+    }
+    /// `area` cut into `columns` by `rows` cells
+    pub fn split(area: Rect, columns: u32, rows: u32, gap: u32) -> Grid {
+        let (columns, rows) = (cmp::max(1, columns), cmp::max(1, rows));
+        Grid {
+            origin: area.top_left(),
+            columns,
+            cell_width: area.width().saturating_sub(gap * (columns - 1)) / columns,
+            cell_height: area.height().saturating_sub(gap * (rows - 1)) / rows,
+            gap,
+        }
+    }
+    pub fn row_height(&self) -> u32 {
+        self.cell_height + self.gap
+    }
+    pub fn rows(&self, count: usize) -> u32 {
+        (count as u32).div_ceil(self.columns)
+    }
+    /// height of `count` cells
+    pub fn height(&self, count: usize) -> u32 {
+        (self.rows(count) * self.row_height()).saturating_sub(self.gap)
+    }
+    pub fn cell(&self, i: usize) -> Rect {
+        let (col, row) = (i as u32 % self.columns, i as u32 / self.columns);
+        Rect::new(self.origin.x() + (col * (self.cell_width + self.gap)) as i32,
+                  self.origin.y() + (row * self.row_height()) as i32,
+                  self.cell_width,
+                  self.cell_height)
+    }
+    /// index of the cell at `p`, `None` in gaps
+    pub fn index_at(&self, p: Point) -> Option<usize> {
+        let (x, y) = (p.x() - self.origin.x(), p.y() - self.origin.y());
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let (col, row) = (x as u32 / (self.cell_width + self.gap), y as u32 / self.row_height());
+        if col >= self.columns || !self.cell((row * self.columns + col) as usize).contains_point(p) {
+            return None;
+        }
+        Some((row * self.columns + col) as usize)
+    }
+}
+
+enum Kind {
+    Row,
+    Column,
+    Grid(u32),
+    Stack,
+}
+
+pub struct Node {
+    name: Option<&'static str>,
+    kind: Kind,
+    width: Length,
+    height: Length,
+    anchor: Anchor,
+    margin: Edges,
+    padding: Edges,
+    gap: u32,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(kind: Kind) -> Node {
+        Node {
+            name: None,
+            kind,
+            width: Length::Fill(1),
+            height: Length::Fill(1),
+            anchor: Anchor::Center,
+            margin: Edges::default(),
+            padding: Edges::default(),
+            gap: 0,
+            children: vec![],
+        }
+    }
+    /// children side by side
+    pub fn row() -> Node {
+        Node::new(Kind::Row)
+    }
+    /// children top to bottom
+    // no view stacks parts vertically yet
+    #[allow(dead_code)]
+    pub fn column() -> Node {
+        Node::new(Kind::Column)
+    }
+    /// children in `columns` equal cells a row
+    // the gallery places its many thumbnails with `Grid` instead of nodes
+    #[allow(dead_code)]
+    pub fn grid(columns: u32) -> Node {
+        Node::new(Kind::Grid(columns))
+    }
+    /// children over each other, also for nodes without children
+    pub fn stack() -> Node {
+        Node::new(Kind::Stack)
+    }
+    pub fn name(mut self, name: &'static str) -> Node {
+        self.name = Some(name);
+        self
+    }
+    pub fn size(mut self, width: Length, height: Length) -> Node {
+        self.width = width;
+        self.height = height;
+        self
+    }
+    pub fn anchor(mut self, anchor: Anchor) -> Node {
+        self.anchor = anchor;
+        self
+    }
+    pub fn margin(mut self, margin: Edges) -> Node {
+        self.margin = margin;
+        self
+    }
+    // the views only use margins so far
+    #[allow(dead_code)]
+    pub fn padding(mut self, padding: Edges) -> Node {
+        self.padding = padding;
+        self
+    }
+    /// between children
+    pub fn gap(mut self, gap: u32) -> Node {
+        self.gap = gap;
+        self
+    }
+    pub fn child(mut self, child: Node) -> Node {
+        self.children.push(child);
+        self
+    }
+
+    /// rects of the named nodes with the tree in `rect`
+    pub fn layout(&self, rect: Rect) -> Rects {
+        let mut rects = Rects(HashMap::new());
+        self.place(rect, &mut rects);
+        rects
+    }
+
+    fn place(&self, slot: Rect, rects: &mut Rects) {
+        let outer = self.margin.shrink(slot);
+        let size = |l: Length, room: u32| match l {
+            Length::Fixed(n) => cmp::min(n, room),
+            Length::Fill(_) => room,
+        };
+        let (w, h) = (size(self.width, outer.width()), size(self.height, outer.height()));
+        let (dx, dy) = self.anchor.offset((outer.width() - w, outer.height() - h));
+        let rect = Rect::new(outer.x() + dx, outer.y() + dy, w, h);
+        if let Some(name) = self.name {
+            rects.0.insert(name, rect);
+        }
+
+        let inner = self.padding.shrink(rect);
+        match self.kind {
+            Kind::Stack => {
+                for c in self.children.iter() {
+                    c.place(inner, rects);
+                }
+            },
+            Kind::Row => {
+                let mut x = inner.x();
+                let main = |c: &Node| (c.width, c.margin.horizontal());
+                for (c, w) in self.children.iter().zip(self.share(inner.width(), main)) {
+                    c.place(Rect::new(x, inner.y(), w, inner.height()), rects);
+                    x += (w + self.gap) as i32;
+                }
+            },
+            Kind::Column => {
+                let mut y = inner.y();
+                let main = |c: &Node| (c.height, c.margin.vertical());
+                for (c, h) in self.children.iter().zip(self.share(inner.height(), main)) {
+                    c.place(Rect::new(inner.x(), y, inner.width(), h), rects);
+                    y += (h + self.gap) as i32;
+                }
+            },
+            Kind::Grid(columns) => {
+                let columns = cmp::max(1, columns);
+                let rows = (self.children.len() as u32).div_ceil(columns);
+                let grid = Grid::split(inner, columns, rows, self.gap);
+                for (i, c) in self.children.iter().enumerate() {
+                    c.place(grid.cell(i), rects);
+                }
+            },
+        }
+    }
+
+    /// slot sizes of the children along the main axis,
+    /// `main` gives a child's length and margin on it
+    fn share<F>(&self, room: u32, main: F) -> Vec<u32>
+        where F: Fn(&Node) -> (Length, u32) {
+        let gaps = self.gap * (self.children.len() as u32).saturating_sub(1);
+        let mut left = room.saturating_sub(gaps);
+        let mut weights = 0;
+        for c in self.children.iter() {
+            let (len, margin) = main(c);
+            let fixed = match len {
+                Length::Fixed(n) => n,
+                Length::Fill(w) => {
+                    weights += w;
+                    0
+                },
+            };
+            left = left.saturating_sub(fixed + margin);
+        }
+        self.children.iter().map(|c| {
+            match main(c) {
+                (Length::Fixed(n), margin) => n + margin,
+                (Length::Fill(w), margin) => margin + (left * w).checked_div(weights).unwrap_or(0),
+            }
+        }).collect()
+    }
+}
+
+/// rects of named nodes
+pub struct Rects(HashMap<&'static str, Rect>);
+
+impl Rects {
+    pub fn get(&self, name: &str) -> Option<Rect> {
+        self.0.get(name).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_and_columns_share_room() {
+        let root = Node::column()
+            .padding(Edges::all(10))
+            .gap(10)
+            .child(Node::row().name("bar").size(Length::Fill(1), Length::Fixed(40))
+                   .child(Node::stack().name("icon").size(Length::Fixed(40), Length::Fill(1)))
+                   .child(Node::stack().name("title").margin(Edges::new(0, 0, 0, 8)))
+                   .child(Node::stack().name("more").size(Length::Fill(2), Length::Fill(1))))
+            .child(Node::stack().name("body"));
+        let rects = root.layout(Rect::new(0, 0, 308, 200));
+        assert_eq!(rects.get("bar"), Some(Rect::new(10, 10, 288, 40)));
+        assert_eq!(rects.get("icon"), Some(Rect::new(10, 10, 40, 40)));
+        // 288 - 40 - 8 = 240 left, one third and two thirds
+        assert_eq!(rects.get("title"), Some(Rect::new(58, 10, 80, 40)));
+        assert_eq!(rects.get("more"), Some(Rect::new(138, 10, 160, 40)));
+        assert_eq!(rects.get("body"), Some(Rect::new(10, 60, 288, 130)));
+        assert_eq!(rects.get("nothing"), None);
+    }
+
+    #[test]
+    fn anchors_in_stack() {
+        let fixed = Length::Fixed(36);
+        let root = Node::stack()
+            .child(Node::stack().name("page"))
+            .child(Node::stack().name("close").size(fixed, fixed).anchor(Anchor::TopRight).margin(Edges::all(10)))
+            .child(Node::stack().name("center").size(fixed, fixed))
+            .child(Node::stack().name("bottom").size(Length::Fill(1), fixed).anchor(Anchor::Bottom));
+        let rects = root.layout(Rect::new(0, 0, 400, 300));
+        assert_eq!(rects.get("page"), Some(Rect::new(0, 0, 400, 300)));
+        assert_eq!(rects.get("close"), Some(Rect::new(354, 10, 36, 36)));
+        assert_eq!(rects.get("center"), Some(Rect::new(182, 132, 36, 36)));
+        assert_eq!(rects.get("bottom"), Some(Rect::new(0, 264, 400, 36)));
+        // rerun for a new size
+        let rects = root.layout(Rect::new(0, 0, 200, 100));
+        assert_eq!(rects.get("close"), Some(Rect::new(154, 10, 36, 36)));
+    }
+
+    #[test]
+    fn grid_cells() {
+        let root = Node::grid(2).gap(10)
+            .child(Node::stack().name("a"))
+            .child(Node::stack().name("b"))
+            .child(Node::stack().name("c"));
+        let rects = root.layout(Rect::new(0, 0, 210, 110));
+        assert_eq!(rects.get("b"), Some(Rect::new(110, 0, 100, 50)));
+        assert_eq!(rects.get("c"), Some(Rect::new(0, 60, 100, 50)));
+
+        // 3 columns of at least 100px fit in 340px
+        let grid = Grid::fit(Rect::new(10, 10, 340, 1), 100, (4, 3), 10);
+        assert_eq!((grid.columns, grid.cell_width, grid.cell_height), (3, 106, 79));
+        assert_eq!(grid.cell(4), Rect::new(126, 99, 106, 79));
+        assert_eq!(grid.height(4), 168);
+        assert_eq!(grid.index_at(Point::new(130, 100)), Some(4));
+        assert_eq!(grid.index_at(Point::new(120, 100)), None);
+        assert_eq!(grid.index_at(Point::new(5, 100)), None);
+        // always one column
+        assert_eq!(Grid::fit(Rect::new(0, 0, 50, 1), 100, (1, 1), 10).columns, 1);
+    }
+}
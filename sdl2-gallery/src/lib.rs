@@ -0,0 +1,13 @@
+// gallery model shared by the app and the `gallery-manifest` tool
+extern crate sdl2;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+#[cfg(target_os = "emscripten")]
+#[macro_use]
+extern crate stdweb;
+
+pub mod model;
+#[cfg(not(target_os = "emscripten"))]
+pub mod manifest;
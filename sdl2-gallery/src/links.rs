@@ -0,0 +1,172 @@
+// Links to the gallery scenes, kept in the browser history.
+// Opening a photo adds a history entry, so the back button returns to the
+// gallery, and the page opens at the scene in the url hash.
+use std::cell::RefCell;
+use std::rc::Rc;
+use actions::Action;
+use config::Config;
+use history::History;
+use navigation::Navigator;
+use scene_transition::Effect;
+use view::{GalleryView, Preview, GALLERY, PREVIEW};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Link {
+    Gallery,
+    /// preview of the image at this index
+    Photo(usize),
+}
+
+impl Link {
+    /// the gallery for unknown paths
+    pub fn parse(path: &str) -> Link {
+        let mut parts = path.split('/').filter(|s| !s.is_empty());
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("photo"), Some(i), None) => i.parse().map(Link::Photo).unwrap_or(Link::Gallery),
+            _ => Link::Gallery,
+        }
+    }
+
+    pub fn path(&self) -> String {
+        match *self {
+            Link::Gallery => "/".to_owned(),
+            Link::Photo(i) => format!("/photo/{}", i),
+        }
+    }
+}
+
+pub struct Links {
+    config: Rc<Config>,
+    history: History,
+    gallery: Rc<RefCell<GalleryView>>,
+    preview: Rc<RefCell<Preview>>,
+    /// path of the current history entry
+    path: RefCell<String>,
+}
+
+impl Links {
+    pub fn new(config: Rc<Config>, history: History, gallery: Rc<RefCell<GalleryView>>, preview: Rc<RefCell<Preview>>) -> Rc<Links> {
+        let path = history.path();
+        Rc::new(Links {
+            config,
+            history,
+            gallery,
+            preview,
+            path: RefCell::new(path),
+        })
+    }
+
+    /// start at the scene in the url, with the gallery below a photo
+    pub fn start(&self, nav: &mut Navigator) {
+        nav.push(GALLERY, ());
+        let link = Link::parse(&self.path.borrow());
+        self.replace(Link::Gallery);
+        if let Link::Photo(i) = link {
+            if self.has_photo(i) && nav.push(PREVIEW, i) {
+                self.push(link);
+            }
+        }
+    }
+
+    /// handles the actions of the gallery scenes
+    pub fn handle(&self, action: &Action, nav: &mut Navigator) -> bool {
+        match *action {
+            Action::ShowPreview(i) => {
+                let shown = nav.push_with(PREVIEW, i, self.zoom(i));
+                if shown {
+                    self.push(Link::Photo(i));
+                }
+                shown
+            },
+            Action::ShowGallery => {
+                let i = self.preview.borrow().current();
+                if nav.pop_with(self.zoom(i)) {
+                    // the popstate that follows finds the gallery shown
+                    self.history.back();
+                    true
+                } else if nav.replace_with(GALLERY, (), Effect::Slide) {
+                    self.replace(Link::Gallery);
+                    true
+                } else {
+                    false
+                }
+            },
+        }
+    }
+
+    /// follow the browser back and forward buttons, call each frame
+    pub fn update(&self, nav: &mut Navigator) {
+        while let Some(path) = self.history.poll() {
+            *self.path.borrow_mut() = path.clone();
+            self.follow(Link::parse(&path), nav);
+        }
+        // the preview pages through photos without new entries
+        if nav.current() == Some(PREVIEW.name) {
+            let link = Link::Photo(self.preview.borrow().current());
+            if link != Link::parse(&self.path.borrow()) {
+                self.replace(link);
+            }
+        }
+    }
+
+    /// show `link` if it is not shown, the history is already there
+    fn follow(&self, link: Link, nav: &mut Navigator) {
+        let shown = if nav.current() == Some(PREVIEW.name) {
+            Some(self.preview.borrow().current())
+        } else {
+            None
+        };
+        match (link, shown) {
+            (Link::Gallery, Some(i)) => {
+                let _ = nav.pop_with(self.zoom(i)) || nav.replace_with(GALLERY, (), Effect::Slide);
+            },
+            (Link::Photo(i), Some(j)) if i != j && self.has_photo(i) => {
+                nav.replace_with(PREVIEW, i, Effect::CrossFade);
+            },
+            (Link::Photo(i), None) if self.has_photo(i) => {
+                nav.push_with(PREVIEW, i, self.zoom(i));
+            },
+            _ => (),
+        }
+    }
+
+    /// zoom from the thumbnail and back to it, if it is in view
+    fn zoom(&self, i: usize) -> Effect {
+        self.gallery.borrow().item_rect(i).map_or(Effect::CrossFade, Effect::Zoom)
+    }
+
+    fn has_photo(&self, i: usize) -> bool {
+        i < self.config.gallery.pics.len()
+    }
+
+    fn push(&self, link: Link) {
+        let path = link.path();
+        self.history.push(&path);
+        *self.path.borrow_mut() = path;
+    }
+
+    fn replace(&self, link: Link) {
+        let path = link.path();
+        self.history.replace(&path);
+        *self.path.borrow_mut() = path;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(Link::parse("/"), Link::Gallery);
+        assert_eq!(Link::parse(""), Link::Gallery);
+        assert_eq!(Link::parse("/photo/12"), Link::Photo(12));
+        assert_eq!(Link::parse("photo/12/"), Link::Photo(12));
+        assert_eq!(Link::parse("/photo/x"), Link::Gallery);
+        assert_eq!(Link::parse("/photo/1/2"), Link::Gallery);
+        assert_eq!(Link::parse("/somewhere"), Link::Gallery);
+        for link in [Link::Gallery, Link::Photo(0), Link::Photo(12)].iter() {
+            assert_eq!(Link::parse(&link.path()), *link);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Image requests waiting to be fetched.
+// At most `max_in_flight` fetches run at once, the rest wait here and
+// start in priority order, lower first. Waiting requests can be cancelled.
+use std::collections::{HashMap, HashSet};
+
+pub struct LoadQueue {
+    max_in_flight: usize,
+    pending: HashMap<String, i64>,
+    in_flight: HashSet<String>,
+}
+
+impl LoadQueue {
+    pub fn new(max_in_flight: usize) -> LoadQueue {
+        LoadQueue {
+            max_in_flight: max_in_flight.max(1),
+            pending: HashMap::new(),
+            in_flight: HashSet::new(),
+        }
+    }
+    /// add a request, or change its priority if waiting already
+    pub fn request(&mut self, src: &str, priority: i64) {
+        if !self.in_flight.contains(src) {
+            self.pending.insert(src.to_owned(), priority);
+        }
+    }
+    /// drop a waiting request, return false if started or unknown
+    pub fn cancel(&mut self, src: &str) -> bool {
+        self.pending.remove(src).is_some()
+    }
+    pub fn is_pending(&self, src: &str) -> bool {
+        self.pending.contains_key(src)
+    }
+    /// next request to start, if a fetch slot is free
+    pub fn next(&mut self) -> Option<String> {
+        if self.in_flight.len() >= self.max_in_flight {
+            return None;
+        }
+        let src = self.pending.iter()
+            .min_by(|a, b| (a.1, a.0).cmp(&(b.1, b.0)))
+            .map(|(src, _)| src.clone())?;
+        self.pending.remove(&src);
+        self.in_flight.insert(src.clone());
+        Some(src)
+    }
+    /// a started fetch ended, with or without success
+    pub fn finished(&mut self, src: &str) {
+        self.in_flight.remove(src);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_limit_and_cancel() {
+        let mut q = LoadQueue::new(2);
+        q.request("far", 300);
+        q.request("view", 0);
+        q.request("gone", 100);
+        q.request("preview", -1);
+
+        assert_eq!(q.next(), Some("preview".to_owned()));
+        // scrolled so "far" is in view now, "gone" is out
+        q.request("far", 0);
+        assert!(q.cancel("gone"));
+        assert_eq!(q.next(), Some("far".to_owned()));
+        assert_eq!(q.next(), None);
+
+        // started requests can not be cancelled, nor requested twice
+        assert!(!q.cancel("far"));
+        q.request("preview", 5);
+        assert!(!q.is_pending("preview"));
+
+        q.finished("preview");
+        assert_eq!(q.next(), Some("view".to_owned()));
+        assert_eq!(q.next(), None);
+    }
+}
@@ -1,33 +1,48 @@
 #![recursion_limit="128"]
 
+#[cfg(target_os = "emscripten")]
 #[macro_use]
 extern crate stdweb;
 extern crate sdl2;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
-#[macro_use]
-extern crate lazy_static;
+extern crate serde_json;
+extern crate toml;
+extern crate sdl2_gallery;
+extern crate main_loop;
 
-mod emscripten;
 mod frame_rate;
 mod utils;
 mod display;
-mod model;
 mod view;
 mod transition;
 mod config;
 mod gesture;
 mod actions;
+mod clock;
+#[cfg(any(test, feature = "record"))]
+mod replay;
+mod texture_cache;
+mod load_queue;
+mod resources;
+mod fetch;
+mod navigation;
+mod scene_transition;
+mod history;
+mod links;
+mod layout;
 
-use std::process;
 use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 // use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::rect::Rect;
-use model::Gallery;
-use view::{GalleryView, Preview};
+use sdl2_gallery::model;
+use main_loop::LoopControl;
+use view::{GalleryView, Preview, GALLERY, PREVIEW};
+use history::History;
+use links::Links;
 use display::{Stage, Display};
 use std::rc::Rc;
 use config::{Config};
@@ -38,18 +53,35 @@ use frame_rate::FrameRate;
 use utils::glyph_renderer::GlyphRenderer;
 #[cfg(feature = "fps")]
 use sdl2::ttf;
+#[cfg(feature = "record")]
+use replay::Recorder;
+#[cfg(feature = "record")]
+use std::fs::File;
 
 fn main() {
-    use emscripten::{emscripten};
+    #[cfg(target_os = "emscripten")]
     stdweb::initialize();
 
-    let gallery: &Gallery = Config::get_gallery().expect("Cannot find gallery config");
+    Config::load(|result| {
+        match result {
+            Ok(config) => {
+                start(Rc::new(config));
+            },
+            Err(e) => println!("{}", e),
+        }
+    });
+
+    // keep runtime alive while config is loading
+    #[cfg(target_os = "emscripten")]
+    stdweb::event_loop();
+}
 
+fn start(config: Rc<Config>) {
     let ctx = sdl2::init().unwrap();
     let _ = image::init(INIT_PNG | INIT_JPG).unwrap();
 
-    let width = *Config::get_u32("width").unwrap();
-    let height = *Config::get_u32("height").unwrap();
+    let width = config.width;
+    let height = config.height;
     let video = ctx.video().unwrap();
 
     // Enable anti-aliasing
@@ -60,6 +92,7 @@ fn main() {
     let window  = match video
         .window("wasm gallery", width, height)
         .position_centered()
+        .resizable()
         .opengl()
         .build() {
             Ok(window) => window,
@@ -74,20 +107,34 @@ fn main() {
     let black = Color::RGB(0, 0, 0);
     let mut events = ctx.event_pump().unwrap();
 
-    let stage = Stage::new(canvas.texture_creator());
-    {
+    let stage = Stage::new(canvas.texture_creator(), config.clone());
+    let links = {
+        let gallery_view = GalleryView::new(stage.clone());
+        let preview = Preview::new(stage.clone());
+        let links = Links::new(config, History::new(), gallery_view.clone(), preview.clone());
         let mut s = stage.borrow_mut();
-        s.add_scene("gallery", GalleryView::new(stage.clone()));
-        s.add_scene("preview", Preview::new(stage.clone()));
-        s.start("gallery");
-    }
+        s.add_scene(GALLERY, gallery_view);
+        s.add_scene(PREVIEW, preview);
+        let l = links.clone();
+        s.add_action_handler(move |action, nav| l.handle(action, nav));
+        links.start(s.navigator());
+        links
+    };
+
+    // record input to replay it in tests
+    #[cfg(feature = "record")]
+    let mut recorder = {
+        let file = File::create("record.jsonl").expect("Cannot create record file");
+        Recorder::new(stage.borrow().clock(), file)
+    };
 
+    // fonts borrow the ttf context, the main loop keeps it for good
     #[cfg(feature = "fps")]
-    let ttf_context = ttf::init().unwrap();
+    let ttf_context: &'static ttf::Sdl2TtfContext = Box::leak(Box::new(ttf::init().unwrap()));
     #[cfg(feature = "fps")]
     let mut glyph_renderer = None;
     #[cfg(feature = "fps")]
-    match ttf_context.load_font("./assets/Supermercado-Regular.ttf", 50) {
+    match ttf_context.load_font("assets/Supermercado-Regular.ttf", 50) {
         Ok(font) => {
             let mut g = GlyphRenderer::new(canvas.texture_creator(), font, Color::RGB(0, 255, 0));
             glyph_renderer = Some(g);
@@ -98,25 +145,41 @@ fn main() {
     }
 
     #[cfg(feature = "fps")]
-    let mut frame_rate = FrameRate::new();
+    let mut frame_rate = FrameRate::new(stage.borrow().clock());
+    #[cfg(feature = "fps")]
+    let mut frame_count = 0u32;
 
-    let main_loop = || {
+    // the main loop owns everything it uses, on the web it outlives this function
+    main_loop::run_main_loop(move || {
         #[cfg(feature = "fps")]
-        frame_rate.tick();
+        {
+            frame_rate.tick();
+            frame_count += 1;
+            if frame_count % 300 == 0 {
+                println!("{}", stage.borrow().resources().texture_stats());
+            }
+        }
 
         for event in events.poll_iter() {
             match event {
                 Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
-                    process::exit(1);
+                    return LoopControl::Exit;
                 },
                 _ => {}
             }
+            #[cfg(feature = "record")]
+            recorder.record_event(&event);
             stage.borrow_mut().handle_events(&event);
         }
+        links.update(stage.borrow_mut().navigator());
         canvas.set_draw_color(black);
         canvas.clear();
-        stage.borrow().update();
-        stage.borrow().render(&mut canvas, Rect::new(0, 0, width, height));
+        #[cfg(feature = "record")]
+        recorder.record_frame();
+        stage.borrow_mut().update();
+        // scenes are laid out again when the window resizes
+        let (w, h) = canvas.window().size();
+        stage.borrow().render(&mut canvas, Rect::new(0, 0, w, h));
 
         // render framerate
         #[cfg(feature = "fps")]
@@ -127,7 +190,6 @@ fn main() {
             }
         }
         canvas.present();
-    };
-
-    emscripten::set_main_loop_callback(main_loop);
+        LoopControl::Continue
+    });
 }
@@ -0,0 +1,143 @@
+// Build a gallery manifest from a directory of images.
+// Each image gets a downscaled png thumbnail, and its size is written
+// to the manifest so the app can lay it out before it loads.
+use std::fs::{self, File};
+use std::path::Path;
+use serde_json;
+use sdl2::image::{LoadSurface, SaveSurface};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::surface::Surface;
+use model::{self, Gallery, GalleryItem};
+
+pub struct ManifestOptions {
+    /// thumbnails dir, relative to the image dir
+    pub thumb_dir: String,
+    /// max thumbnail width and height
+    pub thumb_size: u32,
+    /// keep existing thumbnails newer than their image
+    pub skip_existing: bool,
+}
+
+impl Default for ManifestOptions {
+    fn default() -> Self {
+        ManifestOptions {
+            thumb_dir: "thumbs".to_owned(),
+            thumb_size: 200,
+            skip_existing: true,
+        }
+    }
+}
+
+/// scan `dir` for images and make their thumbnails,
+/// urls in the manifest are relative to `dir`
+pub fn build_manifest<P: AsRef<Path>>(dir: P, options: &ManifestOptions) -> Result<Gallery, String> {
+    let dir = dir.as_ref();
+    let thumb_dir = dir.join(&options.thumb_dir);
+    fs::create_dir_all(&thumb_dir).map_err(|e| format!("{}: {}", thumb_dir.display(), e))?;
+
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && model::is_image(p))
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect();
+    // shorter names first, so img2 comes before img10
+    names.sort_by_key(|n| (n.len(), n.clone()));
+
+    let mut pics = vec![];
+    for name in names {
+        let src = dir.join(&name);
+        let img = match Surface::from_file(&src) {
+            Ok(img) => img,
+            Err(e) => {
+                println!("skip {}: {}", src.display(), e);
+                continue;
+            },
+        };
+
+        let thumb = model::thumb_path(&thumb_dir, &name);
+        if !(options.skip_existing && is_newer(&thumb, &src)) {
+            make_thumbnail(&img, options.thumb_size)?
+                .save(&thumb)
+                .map_err(|e| format!("{}: {}", thumb.display(), e))?;
+        }
+
+        pics.push(GalleryItem {
+            url: name.clone(),
+            preview: model::thumb_path(&options.thumb_dir, &name).to_string_lossy().into_owned(),
+            width: Some(img.width()),
+            height: Some(img.height()),
+        });
+    }
+    Ok(Gallery { pics })
+}
+
+/// write manifest json that `Gallery` deserializes
+pub fn write_manifest<P: AsRef<Path>>(gallery: &Gallery, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let f = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::to_writer_pretty(f, gallery).map_err(|e| e.to_string())
+}
+
+/// fit `img` in a `size` square keeping aspect ratio, never scales up
+pub fn make_thumbnail(img: &Surface, size: u32) -> Result<Surface<'static>, String> {
+    let (w, h) = thumbnail_size(img.width(), img.height(), size);
+    let mut thumb = Surface::new(w, h, PixelFormatEnum::RGB888)?;
+    img.blit_scaled(None, &mut thumb, Rect::new(0, 0, w, h))?;
+    Ok(thumb)
+}
+
+pub fn thumbnail_size(img_w: u32, img_h: u32, size: u32) -> (u32, u32) {
+    if img_w <= size && img_h <= size {
+        return (img_w, img_h);
+    }
+    if img_w > img_h {
+        (size, (img_h as u64 * size as u64 / img_w as u64).max(1) as u32)
+    } else {
+        ((img_w as u64 * size as u64 / img_h as u64).max(1) as u32, size)
+    }
+}
+
+fn is_newer(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a).and_then(|m| m.modified()), fs::metadata(b).and_then(|m| m.modified())) {
+        (Ok(ta), Ok(tb)) => ta >= tb,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumbnail_keeps_aspect_ratio() {
+        assert_eq!(thumbnail_size(800, 600, 200), (200, 150));
+        assert_eq!(thumbnail_size(600, 800, 200), (150, 200));
+        assert_eq!(thumbnail_size(100, 50, 200), (100, 50));
+        assert_eq!(thumbnail_size(4000, 1, 200), (200, 1));
+    }
+
+    #[test]
+    fn manifest_thumbs_found_by_from_dir() {
+        use std::env;
+        let dir = env::temp_dir().join("gallery-manifest-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // same name, other extension
+        fs::copy("static/img/img1.jpg", dir.join("a.jpg")).unwrap();
+        fs::copy("assets/icon.png", dir.join("a.png")).unwrap();
+
+        let g = build_manifest(&dir, &ManifestOptions::default()).unwrap();
+        let previews: Vec<&str> = g.pics.iter().map(|p| p.preview.as_str()).collect();
+        assert_eq!(previews, vec!["thumbs/a.jpg.png", "thumbs/a.png.png"]);
+
+        let found = Gallery::from_dir(&dir).unwrap();
+        let g = g.with_base(&dir);
+        assert_eq!(found.pics.len(), 2);
+        for (a, b) in found.pics.iter().zip(g.pics.iter()) {
+            assert_eq!((&a.url, &a.preview), (&b.url, &b.preview));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
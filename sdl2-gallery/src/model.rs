@@ -1,15 +1,128 @@
-use serde::Deserialize;
+#[cfg(not(target_os = "emscripten"))]
+use std::fs::{self, File};
+#[cfg(not(target_os = "emscripten"))]
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+#[cfg(not(target_os = "emscripten"))]
+use std::path::PathBuf;
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct GalleryItem {
     pub url: String,
     pub preview: String,
+    /// image size, lets layout work before the image loads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
 }
 
-#[derive(Deserialize, Debug)]
+impl GalleryItem {
+    pub fn size(&self) -> Option<(u32, u32)> {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => Some((w, h)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Gallery {
     pub pics: Vec<GalleryItem>,
 }
 
+#[cfg(target_os = "emscripten")]
 js_deserializable!( Gallery );
 
+#[cfg(not(target_os = "emscripten"))]
+const IMAGE_EXTS: [&str; 5] = ["jpg", "jpeg", "png", "gif", "bmp"];
+
+#[cfg(not(target_os = "emscripten"))]
+impl Gallery {
+    /// images in `dir`, previews are taken from `dir/thumbs` if they exist there,
+    /// made by the manifest builder or with the same name as the image
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> io::Result<Gallery> {
+        let dir = dir.as_ref();
+        let thumbs = dir.join("thumbs");
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file() && is_image(p))
+            .collect();
+        // shorter names first, so img2 comes before img10
+        paths.sort_by_key(|p| (p.as_os_str().len(), p.clone()));
+
+        let pics = paths.into_iter().map(|p| {
+            let preview = p.file_name()
+                .and_then(|name| {
+                    let name = name.to_string_lossy();
+                    vec![thumb_path(&thumbs, &name), thumbs.join(&*name)].into_iter().find(|t| t.is_file())
+                })
+                .unwrap_or(p.clone());
+            GalleryItem {
+                url: p.to_string_lossy().into_owned(),
+                preview: preview.to_string_lossy().into_owned(),
+                ..Default::default()
+            }
+        }).collect();
+        Ok(Gallery { pics })
+    }
+
+    /// one image path each line, relative to the list file
+    pub fn from_list<P: AsRef<Path>>(file: P) -> io::Result<Gallery> {
+        let file = file.as_ref();
+        let mut pics = vec![];
+        for line in BufReader::new(File::open(file)?).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            pics.push(GalleryItem { url: line.to_owned(), preview: line.to_owned(), ..Default::default() });
+        }
+        Ok(Gallery { pics }.with_base(file.parent().unwrap_or(Path::new(""))))
+    }
+}
+
+impl Gallery {
+    /// resolve relative image paths against `base`
+    pub fn with_base(mut self, base: &Path) -> Gallery {
+        let join = |s: &str| if s.contains("://") {
+            s.to_owned()
+        } else {
+            base.join(s).to_string_lossy().into_owned()
+        };
+        for p in self.pics.iter_mut() {
+            p.url = join(&p.url);
+            p.preview = join(&p.preview);
+        }
+        self
+    }
+}
+
+/// thumbnail in `dir` the manifest builder makes for image `name`,
+/// `a.jpg` gets `a.jpg.png` so it does not clash with the one of `a.png`
+#[cfg(not(target_os = "emscripten"))]
+pub fn thumb_path<P: AsRef<Path>>(dir: P, name: &str) -> PathBuf {
+    dir.as_ref().join(format!("{}.png", name))
+}
+
+#[cfg(not(target_os = "emscripten"))]
+pub fn is_image(p: &Path) -> bool {
+    p.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| IMAGE_EXTS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gallery_from_dir() {
+        let g = Gallery::from_dir("static/img").unwrap();
+        assert_eq!(g.pics.len(), 31);
+        assert_eq!(g.pics[2].url, "static/img/img2.jpg");
+        assert_eq!(g.pics[10].preview, "static/img/thumbs/img10.jpg");
+    }
+}
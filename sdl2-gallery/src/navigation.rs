@@ -0,0 +1,348 @@
+// Scene stack of the stage.
+// Scenes are registered under a `Route`, which also names the type of the
+// params they start with, so pushing a scene with the wrong params does not
+// compile. Actions returned by scenes go through an `ActionRouter`.
+// Scene changes with an `Effect` are kept until the stage takes them to animate.
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use display::Display;
+use actions::Action;
+use scene_transition::Effect;
+
+/// name of a scene and the type of its params
+pub struct Route<P> {
+    pub name: &'static str,
+    pub params: PhantomData<fn(P)>,
+}
+
+impl<P> Clone for Route<P> {
+    fn clone(&self) -> Route<P> {
+        *self
+    }
+}
+
+impl<P> Copy for Route<P> {}
+
+/// a display the navigator can start with typed params
+pub trait Scene: Display {
+    type Params: 'static;
+    fn on_start(&mut self, params: &Self::Params);
+}
+
+/// calls `Scene::on_start` with the params downcast
+type Start = Box<Fn(&Any)>;
+
+struct Entry {
+    display: Rc<RefCell<Display>>,
+    params: TypeId,
+    start: Start,
+}
+
+struct Frame {
+    name: &'static str,
+    params: Box<Any>,
+}
+
+/// a scene change to animate, `upper` is above `lower` on the stack,
+/// it was pushed if `push` else popped
+pub struct SceneChange {
+    pub lower: Rc<RefCell<Display>>,
+    pub upper: Rc<RefCell<Display>>,
+    pub effect: Effect,
+    pub push: bool,
+}
+
+/// scenes on the stack are paused, the top one is active
+pub struct Navigator {
+    scenes: HashMap<&'static str, Entry>,
+    stack: Vec<Frame>,
+    change: Option<SceneChange>,
+}
+
+impl Navigator {
+    pub fn new() -> Navigator {
+        Navigator {
+            scenes: HashMap::new(),
+            stack: vec![],
+            change: None,
+        }
+    }
+
+    pub fn add_scene<S: Scene + 'static>(&mut self, route: Route<S::Params>, scene: Rc<RefCell<S>>) {
+        let s = scene.clone();
+        let start = move |params: &Any| {
+            if let Some(p) = params.downcast_ref::<S::Params>() {
+                s.borrow_mut().on_start(p);
+            }
+        };
+        self.scenes.insert(route.name, Entry {
+            display: scene,
+            params: TypeId::of::<S::Params>(),
+            start: Box::new(start),
+        });
+    }
+
+    /// a scene is registered for `route` with the same params
+    fn accepts<P: 'static>(&self, route: Route<P>) -> bool {
+        match self.scenes.get(route.name) {
+            Some(e) => e.params == TypeId::of::<P>(),
+            None => false,
+        }
+    }
+
+    /// start a scene above the active one, false if no scene takes these params
+    pub fn push<P: 'static>(&mut self, route: Route<P>, params: P) -> bool {
+        self.push_with(route, params, Effect::Cut)
+    }
+
+    pub fn push_with<P: 'static>(&mut self, route: Route<P>, params: P, effect: Effect) -> bool {
+        if !self.accepts(route) {
+            return false;
+        }
+        let lower = self.active();
+        if let Some(ref top) = lower {
+            top.borrow_mut().on_pause();
+        }
+        self.start(route.name, Box::new(params));
+        self.set_change(lower, effect, true);
+        true
+    }
+
+    /// stop the active scene and start another in its place,
+    /// animated like a push of the new scene
+    pub fn replace_with<P: 'static>(&mut self, route: Route<P>, params: P, effect: Effect) -> bool {
+        if !self.accepts(route) {
+            return false;
+        }
+        let lower = self.active();
+        if let Some(ref top) = lower {
+            top.borrow_mut().on_stop();
+        }
+        self.stack.pop();
+        self.start(route.name, Box::new(params));
+        self.set_change(lower, effect, true);
+        true
+    }
+
+    /// back to the previous scene, false if there is none;
+    /// the last scene is never popped, `effect` plays backwards
+    pub fn pop_with(&mut self, effect: Effect) -> bool {
+        if self.stack.len() < 2 {
+            return false;
+        }
+        let upper = self.active();
+        if let Some(ref top) = upper {
+            top.borrow_mut().on_stop();
+        }
+        let popped = self.stack.pop().unwrap();
+        let (name, params) = {
+            let f = self.stack.last().unwrap();
+            (f.name, &f.params)
+        };
+        if name == popped.name {
+            // same scene shown twice, it only remembers the popped params
+            (self.scenes[name].start)(&**params);
+        } else {
+            self.scenes[name].display.borrow_mut().on_resume();
+        }
+        self.set_change(upper, effect, false);
+        true
+    }
+
+    /// `other` is the scene left, the active one is the other side of the change
+    fn set_change(&mut self, other: Option<Rc<RefCell<Display>>>, effect: Effect, push: bool) {
+        self.change = match (other, self.active(), effect) {
+            (_, _, Effect::Cut) | (None, _, _) | (_, None, _) => None,
+            (Some(other), Some(active), effect) => {
+                let (lower, upper) = if push { (other, active) } else { (active, other) };
+                Some(SceneChange { lower, upper, effect, push })
+            },
+        };
+    }
+
+    /// the last change if it should be animated
+    pub fn take_change(&mut self) -> Option<SceneChange> {
+        self.change.take()
+    }
+
+    fn start(&mut self, name: &'static str, params: Box<Any>) {
+        (self.scenes[name].start)(&*params);
+        self.stack.push(Frame { name, params });
+    }
+
+    pub fn active(&self) -> Option<Rc<RefCell<Display>>> {
+        self.current().map(|name| self.scenes[name].display.clone())
+    }
+
+    /// all scenes, on the stack or not
+    pub fn scenes(&self) -> Vec<Rc<RefCell<Display>>> {
+        self.scenes.values().map(|e| e.display.clone()).collect()
+    }
+
+    /// name of the active scene
+    pub fn current(&self) -> Option<&'static str> {
+        self.stack.last().map(|f| f.name)
+    }
+}
+
+/// handles an action, true if it was handled
+pub type ActionHandler = Box<FnMut(&Action, &mut Navigator) -> bool>;
+
+/// actions go to the handlers in the order they were added
+pub struct ActionRouter {
+    handlers: Vec<ActionHandler>,
+}
+
+impl ActionRouter {
+    pub fn new() -> ActionRouter {
+        ActionRouter { handlers: vec![] }
+    }
+
+    pub fn add<F>(&mut self, handler: F)
+        where F: FnMut(&Action, &mut Navigator) -> bool + 'static {
+        self.handlers.push(Box::new(handler));
+    }
+
+    /// the action back if no handler took it
+    pub fn route(&mut self, action: Action, nav: &mut Navigator) -> Option<Action> {
+        for h in self.handlers.iter_mut() {
+            if h(&action, nav) {
+                return None;
+            }
+        }
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+    use sdl2::render::Canvas;
+    use sdl2::rect::Rect;
+    use sdl2::video::Window;
+
+    /// records its lifecycle calls
+    struct Logged<P> {
+        name: &'static str,
+        log: Rc<RefCell<Vec<String>>>,
+        params: PhantomData<P>,
+    }
+
+    impl<P> Logged<P> {
+        fn new(name: &'static str, log: &Rc<RefCell<Vec<String>>>) -> Rc<RefCell<Logged<P>>> {
+            Rc::new(RefCell::new(Logged { name, log: log.clone(), params: PhantomData }))
+        }
+        fn log(&self, s: &str) {
+            self.log.borrow_mut().push(format!("{} {}", self.name, s));
+        }
+    }
+
+    impl<P> Display for Logged<P> {
+        fn render(&self, _canvas: &mut Canvas<Window>, _rect: Rect) {}
+        fn on_stop(&mut self) { self.log("stop"); }
+        fn on_pause(&mut self) { self.log("pause"); }
+        fn on_resume(&mut self) { self.log("resume"); }
+    }
+
+    impl<P: Debug + 'static> Scene for Logged<P> {
+        type Params = P;
+        fn on_start(&mut self, params: &P) { self.log(&format!("start {:?}", params)); }
+    }
+
+    const LIST: Route<()> = Route { name: "list", params: PhantomData };
+    const DETAIL: Route<usize> = Route { name: "detail", params: PhantomData };
+
+    fn navigator(log: &Rc<RefCell<Vec<String>>>) -> Navigator {
+        let mut nav = Navigator::new();
+        nav.add_scene(LIST, Logged::new("list", log));
+        nav.add_scene(DETAIL, Logged::new("detail", log));
+        nav
+    }
+
+    fn take(log: &Rc<RefCell<Vec<String>>>) -> Vec<String> {
+        log.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn push_pop_replace() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut nav = navigator(&log);
+        assert!(nav.active().is_none());
+        assert!(!nav.pop_with(Effect::Cut));
+
+        assert!(nav.push(LIST, ()));
+        assert!(nav.push(DETAIL, 3));
+        assert_eq!(nav.current(), Some("detail"));
+        assert_eq!(take(&log), vec!["list start ()", "list pause", "detail start 3"]);
+
+        // same scene again, popping starts it with the old params
+        assert!(nav.push(DETAIL, 4));
+        assert!(nav.pop_with(Effect::Cut));
+        assert_eq!(take(&log), vec!["detail pause", "detail start 4", "detail stop", "detail start 3"]);
+
+        assert!(nav.pop_with(Effect::Cut));
+        assert!(!nav.pop_with(Effect::Cut));
+        assert_eq!(nav.stack.len(), 1);
+        assert_eq!(take(&log), vec!["detail stop", "list resume"]);
+
+        assert!(nav.replace_with(DETAIL, 7, Effect::Cut));
+        assert_eq!(nav.stack.len(), 1);
+        assert_eq!(take(&log), vec!["list stop", "detail start 7"]);
+
+        assert!(!nav.push(Route::<()> { name: "missing", params: PhantomData }, ()));
+        // registered with other params
+        assert!(!nav.push(Route::<String> { name: "detail", params: PhantomData }, "x".to_owned()));
+        assert_eq!(nav.current(), Some("detail"));
+    }
+
+    #[test]
+    fn animated_changes() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut nav = navigator(&log);
+        // nothing to animate from
+        nav.push_with(LIST, (), Effect::CrossFade);
+        assert!(nav.take_change().is_none());
+
+        nav.push_with(DETAIL, 1, Effect::Slide);
+        let c = nav.take_change().unwrap();
+        assert!(c.push && c.effect == Effect::Slide);
+        assert!(Rc::ptr_eq(&c.upper, &nav.active().unwrap()));
+        assert!(nav.take_change().is_none());
+
+        nav.pop_with(Effect::CrossFade);
+        let c = nav.take_change().unwrap();
+        assert!(!c.push);
+        assert!(Rc::ptr_eq(&c.lower, &nav.active().unwrap()));
+
+        nav.push(DETAIL, 2);
+        assert!(nav.take_change().is_none());
+    }
+
+    #[test]
+    fn router() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut nav = navigator(&log);
+        nav.push(LIST, ());
+        let mut router = ActionRouter::new();
+        router.add(|a, nav| match a {
+            &Action::ShowPreview(i) => nav.push(DETAIL, i),
+            _ => false,
+        });
+        assert!(router.route(Action::ShowPreview(2), &mut nav).is_none());
+        assert_eq!(nav.current(), Some("detail"));
+        match router.route(Action::ShowGallery, &mut nav) {
+            Some(Action::ShowGallery) => (),
+            _ => panic!("expect unhandled action"),
+        }
+        router.add(|a, nav| match a {
+            &Action::ShowGallery => nav.pop_with(Effect::Cut),
+            _ => false,
+        });
+        assert!(router.route(Action::ShowGallery, &mut nav).is_none());
+        assert_eq!(nav.current(), Some("list"));
+    }
+}
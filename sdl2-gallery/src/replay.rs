@@ -0,0 +1,310 @@
+// A recording is a json line file, each line is either an input event
+// or a frame (an `update` call), stamped with the stage clock time in ms.
+// Replay sets a `ManualClock` to the recorded time before each line,
+// so transitions and slide physics behave the same as when recorded.
+// The app only records (`record` feature), recordings are replayed in tests.
+use std::io::Write;
+#[cfg(test)]
+use std::io::BufRead;
+use std::rc::Rc;
+use std::time::Duration;
+use serde_json;
+use sdl2::event::Event;
+use sdl2::mouse::MouseWheelDirection;
+#[cfg(test)]
+use sdl2::keyboard::{Keycode, Scancode, Mod};
+#[cfg(test)]
+use sdl2::mouse::{MouseButton, MouseState};
+
+use clock::Clock;
+#[cfg(test)]
+use clock::ManualClock;
+#[cfg(test)]
+use display::Display;
+
+/// the subset of `sdl2::event::Event` the gallery reacts to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RecordedEvent {
+    FingerDown { timestamp: u32, touch_id: i64, finger_id: i64, x: f32, y: f32, dx: f32, dy: f32, pressure: f32 },
+    FingerMotion { timestamp: u32, touch_id: i64, finger_id: i64, x: f32, y: f32, dx: f32, dy: f32, pressure: f32 },
+    FingerUp { timestamp: u32, touch_id: i64, finger_id: i64, x: f32, y: f32, dx: f32, dy: f32, pressure: f32 },
+    MultiGesture { timestamp: u32, touch_id: i64, d_theta: f32, d_dist: f32, x: f32, y: f32, num_fingers: u16 },
+    MouseButtonDown { timestamp: u32, which: u32, button: u8, clicks: u8, x: i32, y: i32 },
+    MouseButtonUp { timestamp: u32, which: u32, button: u8, clicks: u8, x: i32, y: i32 },
+    MouseMotion { timestamp: u32, which: u32, state: u32, x: i32, y: i32, xrel: i32, yrel: i32 },
+    MouseWheel { timestamp: u32, which: u32, x: i32, y: i32, flipped: bool },
+    KeyDown { timestamp: u32, keycode: Option<i32>, scancode: Option<i32>, keymod: u16, repeat: bool },
+    KeyUp { timestamp: u32, keycode: Option<i32>, scancode: Option<i32>, keymod: u16, repeat: bool },
+}
+
+impl RecordedEvent {
+    pub fn from_event(evt: &Event) -> Option<RecordedEvent> {
+        let e = match evt {
+            &Event::FingerDown { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+                RecordedEvent::FingerDown { timestamp, touch_id, finger_id, x, y, dx, dy, pressure }
+            },
+            &Event::FingerMotion { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+                RecordedEvent::FingerMotion { timestamp, touch_id, finger_id, x, y, dx, dy, pressure }
+            },
+            &Event::FingerUp { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+                RecordedEvent::FingerUp { timestamp, touch_id, finger_id, x, y, dx, dy, pressure }
+            },
+            &Event::MultiGesture { timestamp, touch_id, d_theta, d_dist, x, y, num_fingers } => {
+                RecordedEvent::MultiGesture { timestamp, touch_id, d_theta, d_dist, x, y, num_fingers }
+            },
+            &Event::MouseButtonDown { timestamp, which, mouse_btn, clicks, x, y, .. } => {
+                RecordedEvent::MouseButtonDown { timestamp, which, button: mouse_btn as u8, clicks, x, y }
+            },
+            &Event::MouseButtonUp { timestamp, which, mouse_btn, clicks, x, y, .. } => {
+                RecordedEvent::MouseButtonUp { timestamp, which, button: mouse_btn as u8, clicks, x, y }
+            },
+            &Event::MouseMotion { timestamp, which, ref mousestate, x, y, xrel, yrel, .. } => {
+                RecordedEvent::MouseMotion { timestamp, which, state: mousestate.to_sdl_state(), x, y, xrel, yrel }
+            },
+            &Event::MouseWheel { timestamp, which, x, y, ref direction, .. } => {
+                let flipped = match direction {
+                    &MouseWheelDirection::Flipped => true,
+                    _ => false,
+                };
+                RecordedEvent::MouseWheel { timestamp, which, x, y, flipped }
+            },
+            &Event::KeyDown { timestamp, keycode, scancode, keymod, repeat, .. } => {
+                RecordedEvent::KeyDown {
+                    timestamp,
+                    keycode: keycode.map(|k| k as i32),
+                    scancode: scancode.map(|s| s as i32),
+                    keymod: keymod.bits(),
+                    repeat,
+                }
+            },
+            &Event::KeyUp { timestamp, keycode, scancode, keymod, repeat, .. } => {
+                RecordedEvent::KeyUp {
+                    timestamp,
+                    keycode: keycode.map(|k| k as i32),
+                    scancode: scancode.map(|s| s as i32),
+                    keymod: keymod.bits(),
+                    repeat,
+                }
+            },
+            _ => return None,
+        };
+        Some(e)
+    }
+
+    #[cfg(test)]
+    pub fn to_event(&self) -> Event {
+        match self {
+            &RecordedEvent::FingerDown { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+                Event::FingerDown { timestamp, touch_id, finger_id, x, y, dx, dy, pressure }
+            },
+            &RecordedEvent::FingerMotion { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+                Event::FingerMotion { timestamp, touch_id, finger_id, x, y, dx, dy, pressure }
+            },
+            &RecordedEvent::FingerUp { timestamp, touch_id, finger_id, x, y, dx, dy, pressure } => {
+                Event::FingerUp { timestamp, touch_id, finger_id, x, y, dx, dy, pressure }
+            },
+            &RecordedEvent::MultiGesture { timestamp, touch_id, d_theta, d_dist, x, y, num_fingers } => {
+                Event::MultiGesture { timestamp, touch_id, d_theta, d_dist, x, y, num_fingers }
+            },
+            &RecordedEvent::MouseButtonDown { timestamp, which, button, clicks, x, y } => {
+                Event::MouseButtonDown { timestamp, window_id: 0, which, mouse_btn: MouseButton::from_ll(button), clicks, x, y }
+            },
+            &RecordedEvent::MouseButtonUp { timestamp, which, button, clicks, x, y } => {
+                Event::MouseButtonUp { timestamp, window_id: 0, which, mouse_btn: MouseButton::from_ll(button), clicks, x, y }
+            },
+            &RecordedEvent::MouseMotion { timestamp, which, state, x, y, xrel, yrel } => {
+                Event::MouseMotion { timestamp, window_id: 0, which, mousestate: MouseState::from_sdl_state(state), x, y, xrel, yrel }
+            },
+            &RecordedEvent::MouseWheel { timestamp, which, x, y, flipped } => {
+                let direction = if flipped { MouseWheelDirection::Flipped } else { MouseWheelDirection::Normal };
+                Event::MouseWheel { timestamp, window_id: 0, which, x, y, direction }
+            },
+            &RecordedEvent::KeyDown { timestamp, keycode, scancode, keymod, repeat } => {
+                Event::KeyDown {
+                    timestamp,
+                    window_id: 0,
+                    keycode: keycode.and_then(Keycode::from_i32),
+                    scancode: scancode.and_then(Scancode::from_i32),
+                    keymod: Mod::from_bits_truncate(keymod),
+                    repeat,
+                }
+            },
+            &RecordedEvent::KeyUp { timestamp, keycode, scancode, keymod, repeat } => {
+                Event::KeyUp {
+                    timestamp,
+                    window_id: 0,
+                    keycode: keycode.and_then(Keycode::from_i32),
+                    scancode: scancode.and_then(Scancode::from_i32),
+                    keymod: Mod::from_bits_truncate(keymod),
+                    repeat,
+                }
+            },
+        }
+    }
+}
+
+/// one line in a recording, `time` is the clock time in ms
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Record {
+    Event { time: u64, event: RecordedEvent },
+    Frame { time: u64 },
+}
+
+fn to_millis(d: Duration) -> u64 {
+    d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000
+}
+
+pub struct Recorder<W: Write> {
+    clock: Rc<Clock>,
+    out: W,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(clock: Rc<Clock>, out: W) -> Recorder<W> {
+        Recorder {
+            clock,
+            out,
+        }
+    }
+    /// record an event before it is passed to `handle_events`
+    pub fn record_event(&mut self, evt: &Event) {
+        if let Some(event) = RecordedEvent::from_event(evt) {
+            let time = to_millis(self.clock.now());
+            self.write(&Record::Event { time, event });
+        }
+    }
+    /// record a frame before `update` is called
+    pub fn record_frame(&mut self) {
+        let time = to_millis(self.clock.now());
+        self.write(&Record::Frame { time });
+    }
+    fn write(&mut self, r: &Record) {
+        let line = serde_json::to_string(r).expect("failed to serialize record");
+        if let Err(e) = writeln!(self.out, "{}", line) {
+            println!("record failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+pub struct Replay {
+    records: Vec<Record>,
+}
+
+#[cfg(test)]
+impl Replay {
+    pub fn new(records: Vec<Record>) -> Replay {
+        Replay {
+            records,
+        }
+    }
+    pub fn load<R: BufRead>(input: R) -> Result<Replay, String> {
+        let mut records = vec![];
+        for (i, line) in input.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim() == "" {
+                continue;
+            }
+            let r = serde_json::from_str(&line)
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            records.push(r);
+        }
+        Ok(Replay::new(records))
+    }
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+    /// feed all records into `display`, `clock` should be the one used by it
+    pub fn run(&self, display: &mut Display, clock: &ManualClock) {
+        for r in &self.records {
+            match r {
+                &Record::Event { time, ref event } => {
+                    clock.set(Duration::from_millis(time));
+                    display.handle_events(&event.to_event());
+                },
+                &Record::Frame { time } => {
+                    clock.set(Duration::from_millis(time));
+                    display.update();
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use sdl2::render::Canvas;
+    use sdl2::rect::Rect;
+    use sdl2::video::Window;
+    use actions::Action;
+    use transition::Transition;
+
+    /// moves a value to 100 in 1 second after it is tapped
+    struct Mover {
+        clock: Rc<Clock>,
+        transition: Option<Transition>,
+        val: i32,
+    }
+
+    impl Display for Mover {
+        fn render(&self, _canvas: &mut Canvas<Window>, _rect: Rect) {}
+        fn handle_events(&mut self, evt: &Event) -> Option<Action> {
+            if let &Event::FingerUp { .. } = evt {
+                self.transition = Some(Transition::new(0, 100, Duration::from_millis(1000), self.clock.clone()));
+            }
+            None
+        }
+        fn update(&mut self) {
+            if let Some(ref mut t) = self.transition {
+                self.val = t.step() as i32;
+            }
+        }
+    }
+
+    fn record() -> Vec<u8> {
+        let clock = Rc::new(ManualClock::new());
+        let mut recorder = Recorder::new(clock.clone(), vec![]);
+        recorder.record_event(&Event::FingerUp { timestamp: 1, touch_id: 0, finger_id: 0, x: 0.5, y: 0.5, dx: 0., dy: 0., pressure: 1. });
+        // not interesting for replay
+        recorder.record_event(&Event::AppTerminating { timestamp: 2 });
+        for _ in 0..5 {
+            clock.advance(Duration::from_millis(100));
+            recorder.record_frame();
+        }
+        recorder.out
+    }
+
+    #[test]
+    fn record_roundtrip() {
+        let replay = Replay::load(Cursor::new(record())).unwrap();
+        assert_eq!(replay.records().len(), 6);
+        assert_eq!(replay.records()[5], Record::Frame { time: 500 });
+        match replay.records()[0] {
+            Record::Event { time: 0, ref event } => {
+                assert_eq!(RecordedEvent::from_event(&event.to_event()).as_ref(), Some(event));
+            },
+            _ => panic!("expect event"),
+        }
+        let key = Event::KeyDown { timestamp: 3, window_id: 0, keycode: Some(Keycode::End), scancode: None, keymod: Mod::empty(), repeat: false };
+        let recorded = RecordedEvent::from_event(&key).unwrap();
+        assert!(recorded.to_event() == key);
+
+        assert!(Replay::load(Cursor::new("{\"Frame\":{}}")).is_err());
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let replay = Replay::load(Cursor::new(record())).unwrap();
+        let mut results = vec![];
+        for _ in 0..2 {
+            let clock = Rc::new(ManualClock::new());
+            let mut mover = Mover { clock: clock.clone(), transition: None, val: 0 };
+            replay.run(&mut mover, &clock);
+            results.push(mover.val);
+        }
+        // easeOutQuad at half of the duration
+        assert_eq!(results, vec![75, 75]);
+    }
+}
@@ -0,0 +1,373 @@
+// Textures and image loads of a stage.
+// `Resources` is a cheap handle to them, the stage owns one and every
+// image keeps a clone, so nothing lives in globals. Borrows are never
+// held across a fetch, native fetch calls back right away.
+// Fetched bytes are decoded in memory, only local assets come from files.
+// Cached textures are destroyed by the context, whose texture creator keeps
+// the renderer alive until then.
+use std::cell::{RefCell, RefMut};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+use sdl2::image::LoadSurface;
+use sdl2::image::ImageRWops;
+use sdl2::render::{Texture, TextureCreator, BlendMode};
+use sdl2::rwops::RWops;
+use sdl2::surface::Surface;
+
+use config::Config;
+use fetch::{self, FetchError, FetchHandle, Response};
+use utils::SizedTexture;
+use texture_cache::{TextureCache, CacheStats};
+use load_queue::LoadQueue;
+
+pub const LOCAL_IMG_PREFIX: &str = "!local:";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadState {
+    Idle,
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+/// loading or failed image
+struct LoadEntry {
+    state: LoadState,
+    /// failures since last success or manual retry
+    attempts: u32,
+    retry_at: Option<Duration>,
+}
+
+/// makes textures of decoded images, for a window or an offscreen canvas
+trait TextureMaker {
+    fn make_texture(&self, surf: Surface) -> Result<Texture, String>;
+}
+
+impl<T> TextureMaker for TextureCreator<T> {
+    fn make_texture(&self, surf: Surface) -> Result<Texture, String> {
+        self.create_texture_from_surface(surf).map_err(|e| e.to_string())
+    }
+}
+
+struct ResourceContext {
+    config: Rc<Config>,
+    /// holds the renderer, textures are freed before it is dropped
+    texture_creator: Box<TextureMaker>,
+    textures: TextureCache<SizedTexture>,
+    loads: HashMap<String, LoadEntry>,
+    queue: LoadQueue,
+    /// id of the running fetch of each image, results of older ones are ignored
+    fetch_ids: HashMap<String, u32>,
+    next_fetch_id: u32,
+    /// to abort running fetches
+    fetches: HashMap<String, FetchHandle>,
+    default_loaded: bool,
+    opacity: f32,
+}
+
+/// textures of the cache belong to the renderer of `texture_creator`,
+/// which it keeps alive, so they can be destroyed as long as the context lives.
+/// with `unsafe_textures` dropping a texture does not free it
+fn destroy_texture(t: SizedTexture) {
+    unsafe { t.2.destroy() }
+}
+
+impl Drop for ResourceContext {
+    /// free the textures still cached, before `texture_creator` goes
+    fn drop(&mut self) {
+        self.textures.clear();
+    }
+}
+
+#[derive(Clone)]
+pub struct Resources(Rc<RefCell<ResourceContext>>);
+
+impl Resources {
+    pub fn new<T: 'static>(tc: TextureCreator<T>, config: Rc<Config>) -> Resources {
+        let mut textures = TextureCache::new(config.texture_budget_mb as usize * 1024 * 1024);
+        textures.set_on_evict(destroy_texture);
+        let queue = LoadQueue::new(config.max_loads as usize);
+        Resources(Rc::new(RefCell::new(ResourceContext {
+            config,
+            texture_creator: Box::new(tc),
+            textures,
+            loads: HashMap::new(),
+            queue,
+            fetch_ids: HashMap::new(),
+            next_fetch_id: 0,
+            fetches: HashMap::new(),
+            default_loaded: false,
+            opacity: 1.,
+        })))
+    }
+
+    pub fn config(&self) -> Rc<Config> {
+        self.0.borrow().config.clone()
+    }
+
+    /// textures for rendering, do not keep across loads
+    pub fn textures(&self) -> RefMut<TextureCache<SizedTexture>> {
+        RefMut::map(self.0.borrow_mut(), |c| &mut c.textures)
+    }
+
+    pub fn is_loaded(&self, src: &str) -> bool {
+        self.0.borrow().textures.contains(src)
+    }
+
+    /// call before rendering each frame
+    pub fn begin_frame(&self) {
+        self.0.borrow_mut().textures.begin_frame();
+    }
+
+    /// multiplies the alpha of images rendered after, to fade whole scenes
+    pub fn set_opacity(&self, opacity: f32) {
+        self.0.borrow_mut().opacity = opacity;
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.0.borrow().opacity
+    }
+
+    /// keep these images loaded, replaces the previously pinned ones
+    pub fn pin_images(&self, srcs: &[&str]) {
+        self.0.borrow_mut().textures.set_pinned(srcs.iter().map(|s| s.to_string()));
+    }
+
+    pub fn texture_stats(&self) -> CacheStats {
+        self.0.borrow().textures.stats()
+    }
+
+    /// shown while images load, loaded once
+    pub fn load_default_img(&self) {
+        let first = {
+            let mut c = self.0.borrow_mut();
+            !::std::mem::replace(&mut c.default_loaded, true)
+        };
+        if first {
+            let src = self.config().default_image.to_owned();
+            self.load_local_img(&src);
+        }
+    }
+
+    /// start loading unless it is loading already or failed and waiting for retry
+    pub fn load_img(&self, src: &str) {
+        self.load_img_with_priority(src, 0);
+    }
+
+    /// lower `priority` loads first, loading images waiting for a fetch get the new priority
+    pub fn load_img_with_priority(&self, src: &str, priority: i64) {
+        if src == "" || self.is_loaded(src) {
+            return;
+        }
+        let state = self.0.borrow().loads.get(src).map(|e| e.state.clone());
+        match state {
+            Some(LoadState::Loading) => {
+                self.0.borrow_mut().queue.request(src, priority);
+                self.pump_loads();
+            },
+            Some(_) => (),
+            None => self.fetch_img(src, priority),
+        }
+    }
+
+    /// forget a load request, aborting its fetch if started
+    pub fn cancel_load(&self, src: &str) {
+        let handle = {
+            let mut c = self.0.borrow_mut();
+            if c.queue.cancel(src) {
+                c.loads.remove(src);
+            }
+            let handle = c.fetches.remove(src);
+            if handle.is_some() {
+                c.fetch_ids.remove(src);
+                c.queue.finished(src);
+                c.loads.remove(src);
+            }
+            handle
+        };
+        if let Some(h) = handle {
+            h.abort();
+        }
+    }
+
+    /// load a failed image again now, with a fresh retry count
+    pub fn retry_img(&self, src: &str) {
+        let failed = match self.0.borrow().loads.get(src) {
+            Some(&LoadEntry { state: LoadState::Failed(_), .. }) => true,
+            _ => false,
+        };
+        if failed {
+            self.0.borrow_mut().loads.remove(src);
+            self.fetch_img(src, 0);
+        }
+    }
+
+    pub fn load_state(&self, src: &str) -> LoadState {
+        if src == "" {
+            return LoadState::Idle;
+        }
+        let c = self.0.borrow();
+        if c.textures.contains(src) {
+            return LoadState::Loaded;
+        }
+        match c.loads.get(src) {
+            Some(e) => e.state.clone(),
+            None => LoadState::Idle,
+        }
+    }
+
+    /// retry failed loads when their backoff is over, call each frame
+    pub fn update_loads(&self, now: Duration) {
+        let config = self.config();
+        let mut due = vec![];
+        for (src, e) in self.0.borrow_mut().loads.iter_mut() {
+            if let LoadState::Failed(_) = e.state {
+                if e.attempts > config.retry_max {
+                    continue;
+                }
+                match e.retry_at {
+                    None => {
+                        // wait twice as long after each failure
+                        let delay = config.retry_delay_ms << (e.attempts - 1).min(16);
+                        e.retry_at = Some(now + Duration::from_millis(delay));
+                    },
+                    Some(t) if t <= now => due.push(src.to_owned()),
+                    _ => (),
+                }
+            }
+        }
+        for src in due {
+            self.fetch_img(&src, 0);
+        }
+        self.pump_loads();
+    }
+
+    pub fn load_local_img(&self, file: &str) {
+        let src = LOCAL_IMG_PREFIX.to_owned() + file;
+        if self.is_loaded(&src) {
+            return;
+        }
+        if let Err(e) = self.loaded(&src, file) {
+            // local assets are not retried
+            self.load_failed(&src, e, false);
+        }
+    }
+
+    fn fetch_img(&self, src: &str, priority: i64) {
+        {
+            let mut c = self.0.borrow_mut();
+            {
+                let e = c.loads.entry(src.to_owned()).or_insert(LoadEntry {
+                    state: LoadState::Loading,
+                    attempts: 0,
+                    retry_at: None,
+                });
+                e.state = LoadState::Loading;
+                e.retry_at = None;
+            }
+            c.queue.request(src, priority);
+        }
+        self.pump_loads();
+    }
+
+    /// start queued fetches while there are free slots
+    fn pump_loads(&self) {
+        loop {
+            let next = self.0.borrow_mut().queue.next();
+            match next {
+                Some(src) => self.start_fetch(&src),
+                None => break,
+            }
+        }
+    }
+
+    fn start_fetch(&self, src: &str) {
+        let id = {
+            let mut c = self.0.borrow_mut();
+            c.next_fetch_id += 1;
+            let id = c.next_fetch_id;
+            c.fetch_ids.insert(src.to_owned(), id);
+            id
+        };
+        // fetches do not keep the resources alive
+        let weak = Rc::downgrade(&self.0);
+        let s = src.to_owned();
+        let handle = fetch::fetch_bytes_with_timeout(src, self.config().fetch_timeout(), move |result| {
+            if let Some(res) = Resources::upgrade(&weak) {
+                res.fetched(&s, id, result);
+            }
+        });
+        let mut c = self.0.borrow_mut();
+        // native fetches are done already
+        if c.fetch_ids.get(src) == Some(&id) {
+            c.fetches.insert(src.to_owned(), handle);
+        }
+    }
+
+    fn fetched(&self, src: &str, id: u32, result: Result<Response, FetchError>) {
+        {
+            let mut c = self.0.borrow_mut();
+            if c.fetch_ids.get(src) != Some(&id) {
+                // cancelled
+                return;
+            }
+            c.fetch_ids.remove(src);
+            c.fetches.remove(src);
+            c.queue.finished(src);
+        }
+        let result = result
+            .map_err(|e| format!("fetch failed: {}", e))
+            .and_then(|rsp| self.loaded_bytes(src, &rsp.body).map_err(|e| {
+                format!("{}, content-type: {}", e, rsp.header("content-type").unwrap_or("unknown"))
+            }));
+        if let Err(e) = result {
+            self.load_failed(src, e, true);
+        }
+    }
+
+    fn upgrade(weak: &Weak<RefCell<ResourceContext>>) -> Option<Resources> {
+        weak.upgrade().map(Resources)
+    }
+
+    /// decode `file` into texture of `src`
+    fn loaded(&self, src: &str, file: &str) -> Result<(), String> {
+        let surf = Surface::from_file(file).map_err(|e| format!("not an image: {}", e))?;
+        self.add_texture(src, surf)
+    }
+
+    /// decode image data into texture of `src`
+    fn loaded_bytes(&self, src: &str, data: &[u8]) -> Result<(), String> {
+        let rw = RWops::from_bytes(data)?;
+        let surf = rw.load().map_err(|e| format!("not an image: {}", e))?;
+        self.add_texture(src, surf)
+    }
+
+    fn add_texture(&self, src: &str, surf: Surface) -> Result<(), String> {
+        let mut c = self.0.borrow_mut();
+        let w = surf.width();
+        let h = surf.height();
+        let mut tex = c.texture_creator.make_texture(surf)?;
+        // allow fading with alpha mod
+        tex.set_blend_mode(BlendMode::Blend);
+        let bpp = tex.query().format.byte_size_per_pixel();
+        // local assets are small and used everywhere, keep them
+        let keep = src.starts_with(LOCAL_IMG_PREFIX);
+        c.textures.insert(src, w, h, bpp, SizedTexture(w, h, tex), keep);
+        c.loads.remove(src);
+        Ok(())
+    }
+
+    fn load_failed(&self, src: &str, reason: String, retry: bool) {
+        println!("load failed! src: {}, {}", src, reason);
+        let mut c = self.0.borrow_mut();
+        let e = c.loads.entry(src.to_owned()).or_insert(LoadEntry {
+            state: LoadState::Loading,
+            attempts: 0,
+            retry_at: None,
+        });
+        e.attempts = if retry { e.attempts + 1 } else { u32::max_value() };
+        e.state = LoadState::Failed(reason);
+        e.retry_at = None;
+    }
+}
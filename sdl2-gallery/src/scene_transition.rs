@@ -0,0 +1,137 @@
+// Animated change between two scenes of the stage.
+// Both scenes are rendered until the animation ends. Effects are described
+// for a push, a pop plays them backwards.
+use std::rc::Rc;
+use std::time::Duration;
+use sdl2::video::Window;
+use sdl2::render::{Canvas, BlendMode};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use clock::Clock;
+use navigation::SceneChange;
+use resources::Resources;
+use transition::{Transition, Easing};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// no animation
+    Cut,
+    CrossFade,
+    /// the upper scene comes in from the right, pushing the lower one out
+    Slide,
+    /// the upper scene grows from this rect to full size over the darkened lower one,
+    /// e.g. the rect of the tapped thumbnail
+    Zoom(Rect),
+}
+
+pub struct SceneTransition {
+    change: SceneChange,
+    transition: Transition<f32>,
+    /// how much of the upper scene is shown, 0 to 1
+    progress: f32,
+}
+
+impl SceneTransition {
+    pub fn new(change: SceneChange, duration: Duration, clock: Rc<Clock>) -> SceneTransition {
+        let (from, to) = if change.push { (0., 1.) } else { (1., 0.) };
+        SceneTransition {
+            change,
+            transition: Transition::with_easing(from, to, duration, Easing::CubicOut, clock),
+            progress: from,
+        }
+    }
+
+    /// false once the animation ends
+    pub fn step(&mut self) -> bool {
+        self.progress = self.transition.step();
+        !self.transition.at_end()
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>, rect: Rect, res: &Resources) {
+        let p = self.progress;
+        let lower = self.change.lower.borrow();
+        let upper = self.change.upper.borrow();
+        match self.change.effect {
+            Effect::Cut => upper.render(canvas, rect),
+            Effect::CrossFade => {
+                res.set_opacity(1. - p);
+                lower.render(canvas, rect);
+                res.set_opacity(p);
+                upper.render(canvas, rect);
+                res.set_opacity(1.);
+            },
+            Effect::Slide => {
+                let dx = (rect.width() as f32 * p) as i32;
+                let mut r = rect;
+                r.offset(-dx, 0);
+                lower.render(canvas, r);
+                r.offset(rect.width() as i32, 0);
+                upper.render(canvas, r);
+            },
+            Effect::Zoom(from) => {
+                lower.render(canvas, rect);
+                canvas.set_blend_mode(BlendMode::Blend);
+                canvas.set_draw_color(Color::RGBA(0, 0, 0, (p * 255.) as u8));
+                let _ = canvas.fill_rect(rect);
+                canvas.set_blend_mode(BlendMode::None);
+                upper.render(canvas, lerp_rect(from, rect, p));
+            },
+        }
+    }
+}
+
+/// rect at `p` of the way from `from` to `to`
+fn lerp_rect(from: Rect, to: Rect, p: f32) -> Rect {
+    let lerp = |a: i32, b: i32| a + ((b - a) as f32 * p).round() as i32;
+    Rect::new(lerp(from.x(), to.x()),
+              lerp(from.y(), to.y()),
+              lerp(from.width() as i32, to.width() as i32).max(1) as u32,
+              lerp(from.height() as i32, to.height() as i32).max(1) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use clock::ManualClock;
+    use display::Display;
+
+    struct Blank;
+
+    impl Display for Blank {
+        fn render(&self, _canvas: &mut Canvas<Window>, _rect: Rect) {}
+    }
+
+    fn change(push: bool) -> SceneChange {
+        SceneChange {
+            lower: Rc::new(RefCell::new(Blank)),
+            upper: Rc::new(RefCell::new(Blank)),
+            effect: Effect::CrossFade,
+            push,
+        }
+    }
+
+    #[test]
+    fn pop_plays_backwards() {
+        let clock = Rc::new(ManualClock::new());
+        let d = Duration::from_millis(100);
+        let mut push = SceneTransition::new(change(true), d, clock.clone());
+        let mut pop = SceneTransition::new(change(false), d, clock.clone());
+        clock.advance(Duration::from_millis(50));
+        assert!(push.step() && pop.step());
+        assert!((push.progress + pop.progress - 1.).abs() < 1e-6);
+        assert!(push.progress > 0.5);
+        clock.advance(Duration::from_millis(50));
+        assert!(!push.step() && !pop.step());
+        assert_eq!((push.progress, pop.progress), (1., 0.));
+    }
+
+    #[test]
+    fn zoom_rect() {
+        let thumb = Rect::new(10, 20, 100, 50);
+        let screen = Rect::new(0, 0, 400, 300);
+        assert_eq!(lerp_rect(thumb, screen, 0.), thumb);
+        assert_eq!(lerp_rect(thumb, screen, 0.5), Rect::new(5, 10, 250, 175));
+        assert_eq!(lerp_rect(thumb, screen, 1.), screen);
+    }
+}
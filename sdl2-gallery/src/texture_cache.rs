@@ -0,0 +1,235 @@
+// Textures kept within a memory budget.
+// Each frame the textures rendered are touched, when the budget is exceeded
+// the least recently rendered ones are dropped, except those rendered in
+// the current frame, pinned ones and the ones kept forever (local assets).
+// Values leaving the cache go to the `on_evict` callback, textures are not
+// freed when dropped, so the owner destroys them there.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// textures and estimated bytes held now
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "textures: {} ({} KB), hits: {}, misses: {}, evictions: {}",
+               self.count, self.bytes / 1024, self.hits, self.misses, self.evictions)
+    }
+}
+
+struct Entry<T> {
+    w: u32,
+    h: u32,
+    value: T,
+    bytes: usize,
+    last_used: u64,
+    keep: bool,
+}
+
+pub struct TextureCache<T> {
+    budget: usize,
+    entries: HashMap<String, Entry<T>>,
+    pinned: HashSet<String>,
+    evicted: HashSet<String>,
+    frame: u64,
+    stats: CacheStats,
+    on_evict: Option<Box<FnMut(T)>>,
+}
+
+impl<T> TextureCache<T> {
+    /// `budget` in bytes
+    pub fn new(budget: usize) -> TextureCache<T> {
+        TextureCache {
+            budget,
+            entries: HashMap::new(),
+            pinned: HashSet::new(),
+            evicted: HashSet::new(),
+            frame: 0,
+            stats: CacheStats::default(),
+            on_evict: None,
+        }
+    }
+    /// called with each evicted or replaced value
+    pub fn set_on_evict<F: FnMut(T) + 'static>(&mut self, f: F) {
+        self.on_evict = Some(Box::new(f));
+    }
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.evict();
+    }
+    /// call before rendering each frame
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+    /// these textures are not evicted until pinned again with another set
+    pub fn set_pinned<I: IntoIterator<Item = String>>(&mut self, keys: I) {
+        self.pinned = keys.into_iter().collect();
+    }
+    /// add a `w`x`h` texture with `bpp` bytes per pixel,
+    /// `keep` ones are never evicted
+    pub fn insert(&mut self, key: &str, w: u32, h: u32, bpp: usize, value: T, keep: bool) {
+        let bytes = w as usize * h as usize * bpp;
+        self.evicted.remove(key);
+        if let Some(old) = self.entries.insert(key.to_owned(), Entry {
+            w, h, value, bytes, keep,
+            last_used: self.frame,
+        }) {
+            self.stats.bytes -= old.bytes;
+            self.stats.count -= 1;
+            self.release(old.value);
+        }
+        self.stats.bytes += bytes;
+        self.stats.count += 1;
+        self.evict();
+    }
+    /// lookup for rendering, marks the texture as used this frame
+    pub fn get(&mut self, key: &str) -> Option<(u32, u32, &T)> {
+        let frame = self.frame;
+        match self.entries.get_mut(key) {
+            Some(e) => {
+                self.stats.hits += 1;
+                e.last_used = frame;
+                Some((e.w, e.h, &e.value))
+            },
+            None => {
+                self.stats.misses += 1;
+                None
+            },
+        }
+    }
+    /// lookup that does not count as use
+    pub fn peek(&self, key: &str) -> Option<(u32, u32, &T)> {
+        self.entries.get(key).map(|e| (e.w, e.h, &e.value))
+    }
+    pub fn peek_mut(&mut self, key: &str) -> Option<(u32, u32, &mut T)> {
+        self.entries.get_mut(key).map(|e| (e.w, e.h, &mut e.value))
+    }
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+    /// release every value, kept ones too
+    pub fn clear(&mut self) {
+        let entries: Vec<Entry<T>> = self.entries.drain().map(|(_, e)| e).collect();
+        self.stats.bytes = 0;
+        self.stats.count = 0;
+        for e in entries {
+            self.release(e.value);
+        }
+    }
+    /// true if dropped from the cache and not inserted since
+    pub fn was_evicted(&self, key: &str) -> bool {
+        self.evicted.contains(key)
+    }
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn evict(&mut self) {
+        if self.stats.bytes <= self.budget {
+            return;
+        }
+        let frame = self.frame;
+        let mut candidates: Vec<(u64, String)> = self.entries.iter()
+            .filter(|&(k, e)| !e.keep && e.last_used < frame && !self.pinned.contains(k))
+            .map(|(k, e)| (e.last_used, k.clone()))
+            .collect();
+        candidates.sort();
+        for (_, k) in candidates {
+            if self.stats.bytes <= self.budget {
+                break;
+            }
+            if let Some(e) = self.entries.remove(&k) {
+                self.stats.bytes -= e.bytes;
+                self.stats.count -= 1;
+                self.stats.evictions += 1;
+                self.evicted.insert(k);
+                self.release(e.value);
+            }
+        }
+    }
+
+    fn release(&mut self, value: T) {
+        if let Some(ref mut f) = self.on_evict {
+            f(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        // room for 3 10x10 textures
+        let mut c = TextureCache::new(1200);
+        c.insert("local", 10, 10, 4, (), true);
+        c.insert("a", 10, 10, 4, (), false);
+        c.insert("b", 10, 10, 4, (), false);
+        c.begin_frame();
+        assert!(c.get("a").is_some());
+        c.begin_frame();
+
+        // b is least recently used
+        c.insert("c", 10, 10, 4, (), false);
+        assert!(!c.contains("b") && c.was_evicted("b"));
+        assert!(c.contains("local") && c.contains("a") && c.contains("c"));
+
+        // a is pinned, c is visible in this frame, so go over budget
+        c.set_pinned(vec!["a".to_owned()]);
+        assert!(c.get("c").is_some());
+        c.insert("d", 10, 10, 4, (), false);
+        assert_eq!(c.stats().count, 4);
+
+        // next frame d is evicted first as c is still visible
+        c.begin_frame();
+        c.get("c");
+        c.set_budget(1200);
+        assert!(!c.contains("d") && c.contains("c"));
+
+        assert!(c.get("b").is_none());
+        let stats = c.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (3, 1, 2));
+        assert_eq!((stats.count, stats.bytes), (3, 1200));
+    }
+
+    #[test]
+    fn evicted_values_are_released() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        let released = Rc::new(RefCell::new(vec![]));
+        let r = released.clone();
+        let mut c = TextureCache::new(800);
+        c.set_on_evict(move |v| r.borrow_mut().push(v));
+        c.insert("a", 10, 10, 4, 1, false);
+        c.insert("b", 10, 10, 4, 2, false);
+        c.begin_frame();
+        // replacing releases the old value
+        c.insert("b", 10, 10, 4, 3, false);
+        assert_eq!(*released.borrow(), vec![2]);
+
+        c.begin_frame();
+        c.insert("c", 10, 10, 4, 4, false);
+        c.insert("d", 10, 10, 4, 5, false);
+        c.begin_frame();
+        c.set_budget(0);
+        let mut v = released.borrow().clone();
+        v.sort();
+        assert_eq!(v, vec![1, 2, 3, 4, 5]);
+        assert_eq!(c.stats().evictions, 4);
+        assert_eq!(c.stats().count, 0);
+
+        // clearing releases the kept ones as well
+        c.insert("local", 10, 10, 4, 6, true);
+        c.clear();
+        assert_eq!(released.borrow().len(), 6);
+        assert_eq!(c.stats().count, 0);
+    }
+}
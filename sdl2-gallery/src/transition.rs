@@ -1,55 +1,305 @@
-use std::time::{Duration, Instant};
+use std::f64::consts::PI;
+use std::rc::Rc;
+use std::time::Duration;
+use clock::Clock;
+
+/// easing functions, map time progress [0, 1] to value progress,
+/// see http://www.gizma.com/easing/ and https://easings.net/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// overshoot the target and settle like a damped spring
+    Spring,
+    Bounce,
+    /// css style cubic-bezier(x1, y1, x2, y2)
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f64) -> f64 {
+        match *self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => -t * (t - 2.),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2. * t * t
+                } else {
+                    -1. + (4. - 2. * t) * t
+                }
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => {
+                let t = t - 1.;
+                t * t * t + 1.
+            },
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    let t = 2. * t - 2.;
+                    t * t * t / 2. + 1.
+                }
+            },
+            Easing::Spring => {
+                1. - (-6. * t).exp() * (3. * PI * t).cos()
+            },
+            Easing::Bounce => bounce(t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let s = solve_bezier(x1, x2, t);
+                bezier(y1, y2, s)
+            },
+        }
+    }
+}
+
+fn bounce(t: f64) -> f64 {
+    let n = 7.5625;
+    let d = 2.75;
+    if t < 1. / d {
+        n * t * t
+    } else if t < 2. / d {
+        let t = t - 1.5 / d;
+        n * t * t + 0.75
+    } else if t < 2.5 / d {
+        let t = t - 2.25 / d;
+        n * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d;
+        n * t * t + 0.984375
+    }
+}
+
+/// one axis of a cubic bezier from (0, 0) to (1, 1)
+fn bezier(p1: f64, p2: f64, s: f64) -> f64 {
+    let r = 1. - s;
+    3. * r * r * s * p1 + 3. * r * s * s * p2 + s * s * s
+}
+
+/// find bezier parameter s where x(s) == x, x(s) is monotonic for x1, x2 in [0, 1]
+fn solve_bezier(x1: f64, x2: f64, x: f64) -> f64 {
+    let (mut lo, mut hi) = (0., 1.);
+    let mut s = x;
+    for _ in 0..30 {
+        let v = bezier(x1, x2, s);
+        if (v - x).abs() < 1e-7 {
+            break;
+        }
+        if v < x {
+            lo = s;
+        } else {
+            hi = s;
+        }
+        s = (lo + hi) / 2.;
+    }
+    s
+}
+
+/// values that can be animated
+pub trait Tween: Copy {
+    /// value at progress `p` from self to `target`, `p` may go past 1 for overshooting easings
+    fn tween(&self, target: &Self, p: f64) -> Self;
+}
+
+impl Tween for i32 {
+    fn tween(&self, target: &Self, p: f64) -> Self {
+        (*self as f64 + (*target - *self) as f64 * p).round() as i32
+    }
+}
+
+impl Tween for f32 {
+    fn tween(&self, target: &Self, p: f64) -> Self {
+        (*self as f64 + (*target - *self) as f64 * p) as f32
+    }
+}
+
+impl Tween for f64 {
+    fn tween(&self, target: &Self, p: f64) -> Self {
+        *self + (*target - *self) * p
+    }
+}
+
+impl<A: Tween, B: Tween> Tween for (A, B) {
+    fn tween(&self, target: &Self, p: f64) -> Self {
+        (self.0.tween(&target.0, p), self.1.tween(&target.1, p))
+    }
+}
+
+impl<A: Tween, B: Tween, C: Tween> Tween for (A, B, C) {
+    fn tween(&self, target: &Self, p: f64) -> Self {
+        (self.0.tween(&target.0, p), self.1.tween(&target.1, p), self.2.tween(&target.2, p))
+    }
+}
 
 enum TransitionState {
     Running,
     AtEnd,
+    Cancelled,
 }
 
-pub struct Transition {
-    start_time: Instant,
+pub struct Transition<T: Tween = i32> {
+    clock: Rc<Clock>,
+    start_time: Duration,
     duration: Duration,
-    start_val: i32,
-    target_val: i32,
+    start_val: T,
+    target_val: T,
+    curr_val: T,
+    easing: Easing,
     state: TransitionState,
+    on_complete: Option<Box<FnMut(&T)>>,
 }
 
-impl Transition {
-    pub fn new(start_val: i32, target_val: i32, duration: Duration) -> Transition {
+impl<T: Tween> Transition<T> {
+    /// transition with easeOutQuad
+    pub fn new(start_val: T, target_val: T, duration: Duration, clock: Rc<Clock>) -> Transition<T> {
+        Transition::with_easing(start_val, target_val, duration, Easing::QuadOut, clock)
+    }
+    pub fn with_easing(start_val: T, target_val: T, duration: Duration, easing: Easing, clock: Rc<Clock>) -> Transition<T> {
         Transition {
-            start_time: Instant::now(),
+            start_time: clock.now(),
+            clock,
             start_val,
             target_val,
+            curr_val: start_val,
             duration,
+            easing,
             state: TransitionState::Running,
+            on_complete: None,
         }
     }
+    /// called with target value once the transition reaches the end
+    pub fn set_on_complete<F: FnMut(&T) + 'static>(&mut self, f: F) {
+        self.on_complete = Some(Box::new(f));
+    }
+    /// true if ended or cancelled
     pub fn at_end(&self) -> bool {
         match self.state {
-            TransitionState::AtEnd => true,
+            TransitionState::Running => false,
+            _ => true,
+        }
+    }
+    pub fn is_cancelled(&self) -> bool {
+        match self.state {
+            TransitionState::Cancelled => true,
             _ => false,
         }
     }
-    pub fn target_val(&self) -> i32 {
+    /// stop at current value, completion callback is not called
+    pub fn cancel(&mut self) {
+        if !self.at_end() {
+            self.state = TransitionState::Cancelled;
+        }
+    }
+    /// go to a new target from current value, the duration restarts
+    pub fn retarget(&mut self, target_val: T) {
+        self.start_val = self.curr_val;
+        self.target_val = target_val;
+        self.start_time = self.clock.now();
+        self.state = TransitionState::Running;
+    }
+    pub fn target_val(&self) -> T {
         self.target_val
     }
-    pub fn step(&mut self) -> f64 {
-        let mut t = Transition::to_f64(self.start_time.elapsed());
-        let d = Transition::to_f64(self.duration);
-        let b = self.start_val;
-        let c = self.target_val - self.start_val;
+    pub fn curr_val(&self) -> T {
+        self.curr_val
+    }
+    pub fn step(&mut self) -> T {
+        match self.state {
+            TransitionState::Running => (),
+            _ => return self.curr_val,
+        }
+        let t = Transition::<T>::to_f64(self.clock.now() - self.start_time);
+        let d = Transition::<T>::to_f64(self.duration);
         if t >= d {
             self.state = TransitionState::AtEnd;
-            return self.target_val as f64;
+            self.curr_val = self.target_val;
+            if let Some(ref mut f) = self.on_complete {
+                f(&self.target_val);
+            }
+            return self.target_val;
         }
-        // using easing fucntions from http://www.gizma.com/easing/
-        // linear
-        // c as f64 * t / d + b as f64
-        // easeOutQuad
-        t /= d;
-	    return -c as f64 * t * (t - 2.) + b as f64;
+        let p = self.easing.apply(t / d);
+        self.curr_val = self.start_val.tween(&self.target_val, p);
+        self.curr_val
     }
     fn to_f64(d: Duration) -> f64 {
         d.as_secs() as f64
             + d.subsec_nanos() as f64 * 1e-9
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use clock::ManualClock;
+
+    #[test]
+    fn easing_ends() {
+        let easings = [Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut,
+                       Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut,
+                       Easing::Bounce, Easing::CubicBezier(0.25, 0.1, 0.25, 1.)];
+        for e in easings.iter() {
+            assert!(e.apply(0.).abs() < 1e-6, "{:?} at 0", e);
+            assert!((e.apply(1.) - 1.).abs() < 1e-6, "{:?} at 1", e);
+        }
+        // spring overshoots and settles
+        assert!(Easing::Spring.apply(0.35) > 1.);
+        assert!((Easing::Spring.apply(1.) - 1.).abs() < 0.01);
+        // bezier with control points on the diagonal is linear
+        assert!((Easing::CubicBezier(0.3, 0.3, 0.7, 0.7).apply(0.4) - 0.4).abs() < 1e-4);
+    }
+
+    #[test]
+    fn tween_tuple() {
+        let clock = Rc::new(ManualClock::new());
+        let mut t = Transition::with_easing((1f32, 0f32, 0f32), (2., 100., -100.), Duration::from_millis(100),
+                                            Easing::Linear, clock.clone());
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(t.step(), (1.5, 50., -50.));
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(t.step(), (2., 100., -100.));
+        assert!(t.at_end());
+    }
+
+    #[test]
+    fn retarget_cancel_and_complete() {
+        let clock = Rc::new(ManualClock::new());
+        let done = Rc::new(Cell::new(0));
+        let mut t = Transition::with_easing(0, 100, Duration::from_millis(100), Easing::Linear, clock.clone());
+        {
+            let done = done.clone();
+            t.set_on_complete(move |v| done.set(*v + 1));
+        }
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(t.step(), 50);
+
+        // continue from 50 to 0
+        t.retarget(0);
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(t.step(), 25);
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(t.step(), 0);
+        assert_eq!(done.get(), 1);
+        assert!(t.at_end() && !t.is_cancelled());
+
+        let mut t = Transition::new(0, 100, Duration::from_millis(100), clock.clone());
+        {
+            let done = done.clone();
+            t.set_on_complete(move |_| done.set(-1));
+        }
+        clock.advance(Duration::from_millis(50));
+        let v = t.step();
+        t.cancel();
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(t.step(), v);
+        assert!(t.is_cancelled());
+        assert_eq!(done.get(), 1);
+    }
+}
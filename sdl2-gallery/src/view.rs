@@ -1,144 +1,235 @@
-use std::any::Any;
 use std::f32::consts::PI;
 use std::rc::{Rc, Weak};
 use std::cell::{RefCell};
 use std::time::{Duration};
-use display::{Image, Button, Stage, Display, FillMode};
+use std::ops::Range;
+use std::marker::PhantomData;
+use display::{Image, Button, Stage, Display, FillMode, LoadState};
+use resources::Resources;
 use model::Gallery;
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
 use sdl2::rect::{Rect, Point};
 use sdl2::event::Event;
-use sdl2::touch::num_touch_fingers;
+use sdl2::keyboard::Keycode;
 use transition::Transition;
-use gesture::{GestureDetector, GestureEvent, GestureDetectorTypes};
+use clock::{self, Clock, FrameTimer};
+use gesture::{GestureDetector, GestureEvent, GestureDetectorTypes, GestureConfig, SwipeDirection};
 use utils::mean::Mean;
 use config::{Config};
 use actions::Action;
+use navigation::{Route, Scene};
+use layout::{Grid, Node, Edges, Length, Anchor};
 
-const FRICTION: f32 = 2.;
-const THUMB_W: u32 = 100;
-const THUMB_H: u32 = 100;
-const THUMB_GAP: u32 = 10;
+const WHEEL_STEP: f32 = 60.; // px scrolled by each wheel tick
+const WHEEL_ZOOM: f32 = 1.1; // scale ratio of each wheel tick
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 5.;
+const RUBBER_BAND_ZOOM: f32 = 0.3; // damp zoom ratio past zoom limits
 
-struct GalleryLayout {
-    n: u32,   // n items each row
-    item_width: u32, // each item with width
-    item_height: u32, // each item with height
-    scroll_height: u32, // scroll content height
-    max_scroll: i32,
-}
+pub const GALLERY: Route<()> = Route { name: "gallery", params: PhantomData };
+pub const PREVIEW: Route<usize> = Route { name: "preview", params: PhantomData };
 
 pub struct GalleryView {
     parent: Weak<RefCell<Stage>>,
+    clock: Rc<Clock>,
+    config: Rc<Config>,
     images: Vec<Rc<RefCell<Image>>>,
     dragging: bool,
     translate_y: f32,
     gesture_detector: GestureDetector,
-    transition: Option<Transition>,
-    layout: GalleryLayout,
+    transition: Option<Transition<f32>>,
+    rect: Rect,
+    grid: Grid, // thumbnail cells, not scrolled
+    max_scroll: i32,
     mean_y: Mean<f32>,     // mean are to track mean move speed
-    dy: f32, // verticle move speed
+    dy: f32, // verticle move speed, px per 60fps frame
+    pan_time: u32, // timestamp of last pan event
+    frame_timer: FrameTimer,
+    requested: Range<usize>, // images requested to load
+    last_y: f32, // translate_y when images were last requested
+    scroll_dir: f32, // sign of last scroll move
 }
 
 impl GalleryView {
     pub fn new(parent: Rc<RefCell<Stage>>) -> Rc<RefCell<GalleryView>> {
-        let config = Config::get_gallery().unwrap();
-        let images = config.pics.iter().map(|ref p| {
-            let img = Image::new_with_dimension(p.preview.to_owned(), THUMB_W, THUMB_H);
+        let config = parent.borrow().config();
+        let res = parent.borrow().resources();
+        let images = config.gallery.pics.iter().map(|ref p| {
+            let img = Image::new_with_dimension(res.clone(), p.preview.to_owned(), config.thumb_width, config.thumb_height);
             img.borrow_mut().set_fill(FillMode::Cover);
             img
         }).collect();
 
+        let width = config.width;
+        let height = config.height;
+        let rect = Rect::new(0, 0, width, height);
         let mut g = GalleryView {
+            clock: parent.borrow().clock(),
             parent: Rc::downgrade(&parent),
             images,
             dragging: false,
             translate_y: 0.,
-            gesture_detector: GestureDetector::new(
+            gesture_detector: GestureDetector::with_config(
                 vec![GestureDetectorTypes::Pan,
-                     GestureDetectorTypes::Tap]),
+                     GestureDetectorTypes::Tap,
+                     GestureDetectorTypes::LongPress,
+                     GestureDetectorTypes::Wheel],
+                GestureConfig { screen_size: Some((width, height)), ..Default::default() }),
             transition: None,
-            layout: GalleryView::get_row_layout(config.pics.len()),
+            rect,
+            grid: GalleryView::grid(&config, rect),
+            max_scroll: 0,
             mean_y: Mean::new(3),
             dy: 0.,
+            pan_time: 0,
+            frame_timer: FrameTimer::new(parent.borrow().clock()),
+            requested: 0..0,
+            last_y: 0.,
+            scroll_dir: 0.,
+            config,
         };
-        g.load_images_inview();
+        g.layout(rect);
         Rc::new(RefCell::new(g))
     }
-    fn get_row_layout(count: usize) -> GalleryLayout {
-        let width = *Config::get_u32("width").unwrap();
-        let height = *Config::get_u32("height").unwrap();
-
-        // n items each row
-        let n = ((width as f32 - THUMB_GAP as f32) / (THUMB_GAP + THUMB_W) as f32).floor() as u32;
-        let w = (width - THUMB_GAP) / n - THUMB_GAP;
-        let h = w;
-        let scroll_height = (count as f32 / n as f32).ceil() as u32 * (h + THUMB_GAP) + THUMB_GAP;
-        GalleryLayout{n, item_width: w, item_height: h, scroll_height, max_scroll: (scroll_height as i32 - height as i32).max(0)}
+    /// as many thumbnail columns as fit, with a gap around them
+    fn grid(config: &Config, rect: Rect) -> Grid {
+        let gap = config.thumb_gap;
+        Grid::fit(Edges::all(gap).shrink(rect), config.thumb_width, (config.thumb_width, config.thumb_height), gap)
     }
+    /// `x`, `y` in the scrolled content
     fn image_under_point(&self, x: i32, y: i32) -> Option<usize> {
-        let GalleryLayout{ n, item_width: w, item_height: h, .. } = self.layout;
-        for i in 0..self.images.len() {
-            let (rx, ry) = self.item_center(n, w, h, i);
-            let r = Rect::from_center(Point::new(rx as i32, ry as i32), w, h);
-            if r.contains_point(Point::new(x, y)) {
-                return Some(i)
-            }
-        }
-        None
+        self.grid.index_at(Point::new(x, y)).filter(|&i| i < self.images.len())
     }
-    fn item_center(&self, n: u32, w: u32, h: u32, i: usize) -> (u32, u32) {
-        let i: u32 = i as u32;
-        let x = THUMB_GAP + w / 2 + (w + THUMB_GAP) * (i % n);
-        let y = THUMB_GAP + h / 2 + (h + THUMB_GAP) * (i / n);
-        (x, y)
+    /// rect of the thumbnail on screen, `None` if it is out of view
+    pub fn item_rect(&self, i: usize) -> Option<Rect> {
+        if i >= self.images.len() {
+            return None;
+        }
+        let mut r = self.grid.cell(i);
+        r.offset(0, self.translate_y as i32);
+        if r.top() >= self.rect.top() && r.bottom() <= self.rect.bottom() {
+            Some(r)
+        } else {
+            None
+        }
     }
     fn move_by(&mut self, dy: f32) {
         let ty = self.translate_y + dy;
         let d;
         if ty > 0. && dy > 0. {
             d = ty / 100.;
-        } else if ty < -self.layout.max_scroll as f32 {
-            d = (- self.layout.max_scroll as f32 - ty) / 100.;
+        } else if ty < -self.max_scroll as f32 {
+            d = (- self.max_scroll as f32 - ty) / 100.;
         } else {
             d = 0.;
         }
 
-        // get a mean to calc motion speed
-        self.mean_y.push(dy);
-
-        self.dy = self.mean_y.get() as f32;
         // apply damping past border
         let dy = dy * if d < PI / 2. { d.cos() } else { 0. };
         self.translate_y += dy;
     }
-    fn load_images_inview(&self) {
-        let h = (THUMB_GAP + self.layout.item_height) as f32;
-        let n = self.layout.n as usize;
-        let height = *Config::get_u32("height").unwrap();
+    /// indices of images in view
+    fn inview_range(&self) -> Range<usize> {
+        let h = self.grid.row_height() as f32;
+        let n = self.grid.columns as usize;
+        let height = self.rect.height();
         let rs = (-self.translate_y / h).max(0.) as usize;
-        let re = ((-self.translate_y + height as f32 - THUMB_GAP as f32) / h).max(0.).ceil() as usize;
-        // [rs, re) row should be loaded
-        for i in rs*n .. re*n {
-            if i < self.images.len() {
-                self.images[i].borrow().load();
+        let re = ((-self.translate_y + height as f32 - self.grid.gap as f32) / h).max(0.).ceil() as usize;
+        // [rs, re) row are in view
+        (rs * n).min(self.images.len()) .. (re * n).min(self.images.len())
+    }
+    /// request images in view and the next rows in scroll direction,
+    /// nearer ones load first, requests no longer wanted are cancelled
+    fn load_images_inview(&mut self) {
+        let moved = self.translate_y - self.last_y;
+        if moved != 0. {
+            self.scroll_dir = moved.signum();
+        }
+        self.last_y = self.translate_y;
+
+        let n = self.grid.columns as usize;
+        let len = self.images.len();
+        let view = self.inview_range();
+        let ahead = self.config.prefetch_rows as usize * n;
+        let wanted = if self.scroll_dir < 0. {
+            // content moves up, rows below come into view
+            view.start .. (view.end + ahead).min(len)
+        } else if self.scroll_dir > 0. {
+            view.start.saturating_sub(ahead) .. view.end
+        } else {
+            view.clone()
+        };
+        if wanted == self.requested {
+            return;
+        }
+
+        for i in self.requested.clone() {
+            if i < wanted.start || i >= wanted.end {
+                self.images[i].borrow().cancel_load();
             }
         }
+        let row_h = self.grid.row_height() as i64;
+        for i in wanted.clone() {
+            // px away from view
+            let rows = if i < view.start {
+                (view.start - i).div_ceil(n)
+            } else if i >= view.end {
+                (i - view.end) / n + 1
+            } else {
+                0
+            };
+            self.images[i].borrow().load_with_priority(rows as i64 * row_h);
+        }
+        self.requested = wanted;
+    }
+    pub fn load_state(&self, i: usize) -> Option<LoadState> {
+        self.images.get(i).map(|img| img.borrow().load_state())
+    }
+    /// load failed images in view again
+    pub fn retry_failed(&self) {
+        for i in self.inview_range() {
+            self.images[i].borrow().retry();
+        }
+    }
+    /// animate to scroll position y, limited to scroll range
+    fn scroll_to(&mut self, y: i32) {
+        let y = y.min(0).max(-self.max_scroll) as f32;
+        self.dy = 0.;
+        if let Some(ref mut transition) = self.transition {
+            // keep moving smoothly from where the running transition is
+            if !transition.at_end() {
+                transition.retarget(y);
+                return;
+            }
+        }
+        self.transition = Some(Transition::new(self.translate_y,
+                                               y,
+                                               self.config.transition_duration(),
+                                               self.clock.clone()));
+    }
+    /// where the scroll stops, take running transition into account
+    fn scroll_target(&self) -> i32 {
+        match self.transition {
+            Some(ref transition) => transition.target_val() as i32,
+            None => self.translate_y as i32,
+        }
     }
     fn snap_to_border(&mut self) {
-        let min_y = -self.layout.max_scroll;
+        let min_y = -self.max_scroll as f32;
         if self.translate_y > 0. {
             // below top
-            self.transition = Some(Transition::new(self.translate_y as i32,
-                                                   0,
-                                                   Duration::from_millis(300)));
+            self.transition = Some(Transition::new(self.translate_y,
+                                                   0.,
+                                                   self.config.transition_duration(),
+                                                   self.clock.clone()));
             self.dy = 0.; // snap does not need slide behavior
-        } else if self.translate_y < min_y as f32 {
+        } else if self.translate_y < min_y {
             // above bottom
-            self.transition = Some(Transition::new(self.translate_y as i32,
+            self.transition = Some(Transition::new(self.translate_y,
                                                    min_y,
-                                                   Duration::from_millis(300)));
+                                                   self.config.transition_duration(),
+                                                   self.clock.clone()));
             self.dy = 0.; // snap does not need slide behavior
         }
     }
@@ -148,6 +239,15 @@ impl Display for GalleryView {
         true
     }
     fn update(&mut self) {
+        self.load_images_inview();
+        // a finger held still long enough
+        self.gesture_detector.update(clock::timestamp(self.clock.now()));
+        for event in self.gesture_detector.poll() {
+            if let GestureEvent::LongPress(..) = event {
+                self.retry_failed();
+            }
+        }
+        let frames = self.frame_timer.tick();
         let mut in_transition = !self.dragging && self.transition.is_some();
         if in_transition {
             // transition back to border after drag
@@ -155,9 +255,9 @@ impl Display for GalleryView {
                 if transition.at_end() {
                     // end transition
                     in_transition = false;
-                    self.translate_y = transition.target_val() as f32;
+                    self.translate_y = transition.target_val();
                 } else {
-                    self.translate_y = transition.step() as f32;
+                    self.translate_y = transition.step();
                 }
             }
             if !in_transition {
@@ -169,19 +269,18 @@ impl Display for GalleryView {
             // apply more friction if sliding past border
             let f = if ty > 0. {
                 ty
-            } else if ty < -self.layout.max_scroll as f32 {
-                - self.layout.max_scroll as f32 - ty
+            } else if ty < -self.max_scroll as f32 {
+                - self.max_scroll as f32 - ty
             } else {
                 0.
             } / 5.;
 
-            let dy = apply_friction(f + FRICTION, self.dy);
-            self.move_by(dy);
+            let (d, dy) = slide(f + self.config.friction, self.dy, frames);
+            self.move_by(d);
             self.dy = dy;
 
             if self.dy.abs() == 0. {
                 // slide stopped
-                self.load_images_inview();
                 self.snap_to_border();
                 return;
             }
@@ -193,39 +292,86 @@ impl Display for GalleryView {
         for ref event in self.gesture_detector.poll() {
             match event {
                 &GestureEvent::Tap(x, y) => {
-                    let x = x * (*Config::get_u32("width").unwrap()) as f32;
-                    let y = y * (*Config::get_u32("height").unwrap()) as f32;
+                    let x = x * self.rect.width() as f32;
+                    let y = y * self.rect.height() as f32;
                     let i = self.image_under_point(x as i32, y as i32 - self.translate_y as i32);
 
                     if let Some(ii) = i {
+                        // tap on a failed thumbnail loads it again
+                        if let LoadState::Failed(_) = self.images[ii].borrow().load_state() {
+                            self.retry_failed();
+                            continue;
+                        }
                         return Some(Action::ShowPreview(ii));
                     }
                 },
-                &GestureEvent::PanStart { .. } => {
+                &GestureEvent::LongPress(..) => {
+                    self.retry_failed();
+                },
+                &GestureEvent::PanStart { timestamp, .. } => {
                     self.dragging = true;
+                    self.pan_time = timestamp;
                 },
-                &GestureEvent::Pan { dy, .. } => {
-                    let height = *Config::get_u32("height").unwrap();
+                &GestureEvent::Pan { dy, timestamp, .. } => {
+                    let height = self.rect.height();
                     let dy = dy as f32 * height as f32;
                     self.move_by(dy);
+
+                    // get a mean to calc motion speed
+                    let frames = clock::frames_between(self.pan_time, timestamp);
+                    self.pan_time = timestamp;
+                    self.mean_y.push(dy / frames);
+                    self.dy = self.mean_y.get() as f32;
                 },
                 &GestureEvent::PanEnd { .. } => {
                     self.dragging = false;
                     self.snap_to_border();
                 },
+                &GestureEvent::Wheel { dy, .. } => {
+                    let y = self.scroll_target() + (dy * WHEEL_STEP) as i32;
+                    self.scroll_to(y);
+                },
                 _ => ()
             }
         }
+
+        if let &Event::KeyDown { keycode: Some(key), .. } = evt {
+            let height = self.rect.height() as i32;
+            let row = self.grid.row_height() as i32;
+            let y = self.scroll_target();
+            match key {
+                Keycode::Up => self.scroll_to(y + row),
+                Keycode::Down => self.scroll_to(y - row),
+                Keycode::PageUp => self.scroll_to(y + height),
+                Keycode::PageDown => self.scroll_to(y - height),
+                Keycode::Home => self.scroll_to(0),
+                Keycode::End => self.scroll_to(-self.max_scroll),
+                _ => (),
+            }
+        }
         None
     }
+    /// thumbnails fill the width of `rect`, they scroll in its height
+    fn layout(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.grid = GalleryView::grid(&self.config, rect);
+        let gap = self.grid.gap;
+        let scroll_height = self.grid.height(self.images.len()) + 2 * gap;
+        self.max_scroll = (scroll_height as i32 - rect.height() as i32).max(0);
+        self.gesture_detector.set_screen_size(rect.size());
+        // keep in scroll range
+        self.transition = None;
+        self.translate_y = self.translate_y.min(0.).max(-self.max_scroll as f32);
+        self.load_images_inview();
+    }
     fn render(&self, canvas: &mut Canvas<Window>, rect: Rect) {
-        let GalleryLayout{ n, item_width: w, item_height: h, .. } = self.layout;
+        // `rect` is moved from the layout one during scene transitions
+        let (dx, dy) = (rect.x() - self.rect.x(), rect.y() - self.rect.y() + self.translate_y as i32);
 
         canvas.set_clip_rect(rect);
         for (i, img) in self.images.iter().enumerate() {
-            let (x, y) = self.item_center(n, w, h, i);
-            let y = y as i32 + self.translate_y as i32;
-            let r = Rect::from_center(Point::new(x as i32, y), w, h);
+            let mut r = self.grid.cell(i);
+            r.offset(dx, dy);
             if r.bottom() > rect.top() && r.top() < rect.bottom() {
                 img.borrow().render(canvas, r);
             }
@@ -234,10 +380,22 @@ impl Display for GalleryView {
     }
 }
 
+impl Scene for GalleryView {
+    type Params = ();
+    fn on_start(&mut self, _params: &()) {}
+}
+
 const PREVIEW_GAP: i32 = 30;
+const BUTTON_SIZE: u32 = 36;
+// preview images load before gallery thumbnails, the shown one first
+const CURRENT_PRIORITY: i64 = -2;
+const NEIGHBOUR_PRIORITY: i64 = -1;
 
 pub struct Preview {
     parent: Weak<RefCell<Stage>>,
+    clock: Rc<Clock>,
+    config: Rc<Config>,
+    resources: Resources,
     prev: Rc<RefCell<ScrollView>>,
     curr: Rc<RefCell<ScrollView>>,
     next: Rc<RefCell<ScrollView>>,
@@ -246,33 +404,37 @@ pub struct Preview {
     dragging: bool,
     translate_x: i32,
     translate_x_pre: i32,
+    swipe: Option<SwipeDirection>, // swipe detected in current pan
+    pan_time: u32, // timestamp of last pan event
+    frame_timer: FrameTimer,
     img_idx: usize,
     transition: Option<Transition>,
     gesture_detector: GestureDetector,
     back_btn: Button,
+    chrome: Node,
 }
 
 impl Preview {
     pub fn new(parent: Rc<RefCell<Stage>>) -> Rc<RefCell<Preview>> {
-
-        let width = *Config::get_u32("width").unwrap();
-        let height = *Config::get_u32("height").unwrap();
-
-        let prev = ScrollView::new(Image::new_with_dimension("".to_owned(), width, height));
-        prev.borrow_mut().set_rect(0, 0, width, height);
-
-        let curr = ScrollView::new(Image::new_with_dimension("".to_owned(), width, height));
-        curr.borrow_mut().set_rect(0, 0, width, height);
-
-        let next = ScrollView::new(Image::new_with_dimension("".to_owned(), width, height));
-        next.borrow_mut().set_rect(0, 0, width, height);
-
-        let size = 36_u32;
-        let mut back_btn = Button::new(Rect::new(width as i32 - size as i32 - 10, 10, size, size));
-        let img = Image::new_with_dimension_local("../assets/list.png".to_owned(), size, size);
+        let config = parent.borrow().config();
+        let width = config.width;
+        let height = config.height;
+        let clock = parent.borrow().clock();
+        let res = parent.borrow().resources();
+
+        let prev = ScrollView::new(Preview::image(res.clone(), width, height, clock.clone()), clock.clone(), config.clone());
+        let curr = ScrollView::new(Preview::image(res.clone(), width, height, clock.clone()), clock.clone(), config.clone());
+        let next = ScrollView::new(Preview::image(res.clone(), width, height, clock.clone()), clock.clone(), config.clone());
+
+        let mut back_btn = Button::new(Rect::new(0, 0, BUTTON_SIZE, BUTTON_SIZE), (width, height));
+        let img = Image::new_with_dimension_local(res.clone(), "assets/list.png".to_owned(), BUTTON_SIZE, BUTTON_SIZE);
         back_btn.set_img(img);
 
+        let gesture_config = GestureConfig { screen_size: Some((width, height)), ..Default::default() };
         let mut g = Preview {
+            clock: parent.borrow().clock(),
+            config,
+            resources: res,
             parent: Rc::downgrade(&parent),
             prev,
             curr,
@@ -282,25 +444,47 @@ impl Preview {
             dragging: false,
             translate_x: 0,
             translate_x_pre: 0,
+            swipe: None,
+            pan_time: 0,
+            frame_timer: FrameTimer::new(parent.borrow().clock()),
             img_idx: 0,
             transition: None,
-            gesture_detector: GestureDetector::new(
-                vec![GestureDetectorTypes::Pan,
-                     GestureDetectorTypes::Tap]),
+            // swipe goes before pan, so it is known when pan ends
+            gesture_detector: GestureDetector::with_config(
+                vec![GestureDetectorTypes::Swipe,
+                     GestureDetectorTypes::Pan,
+                     GestureDetectorTypes::Tap,
+                     GestureDetectorTypes::Pinch,
+                     GestureDetectorTypes::LongPress,
+                     GestureDetectorTypes::Wheel],
+                gesture_config),
             back_btn,
+            chrome: Preview::chrome(),
         };
+        g.layout(Rect::new(0, 0, width, height));
         Rc::new(RefCell::new(g))
     }
 
+    /// pages fill the screen, buttons are in a bar on top
+    fn chrome() -> Node {
+        let button = Length::Fixed(BUTTON_SIZE);
+        Node::stack()
+            .child(Node::stack().name("page"))
+            .child(Node::row().size(Length::Fill(1), button).anchor(Anchor::Top).margin(Edges::all(10)).gap(10)
+                   .child(Node::stack())
+                   .child(Node::stack().name("back").size(button, button)))
+    }
+
     fn rotate(&mut self) {
         println!("rotate with translate_x: {}", self.translate_x);
         let p = self.img_idx as isize - 1;
-        let config = Config::get_gallery().unwrap();
+        let config = self.config.clone();
+        let gallery = &config.gallery;
         if self.translate_x > 0 && p >= 0 {
             println!("rotate left");
             self.translate_x -= self.width as i32 + PREVIEW_GAP;
             self.set_curr_image(p as usize);
-        } else if self.translate_x < 0 && self.img_idx + 1 < config.pics.len() {
+        } else if self.translate_x < 0 && self.img_idx + 1 < gallery.pics.len() {
             println!("rotate right");
             self.translate_x += self.width as i32 + PREVIEW_GAP;
             let i = self.img_idx + 1;
@@ -308,10 +492,18 @@ impl Preview {
         }
     }
 
+    /// full image that fades in over its thumbnail
+    fn image(res: Resources, width: u32, height: u32, clock: Rc<Clock>) -> Rc<RefCell<Image>> {
+        let img = Image::new_with_dimension(res, "".to_owned(), width, height);
+        img.borrow_mut().set_clock(clock);
+        img
+    }
+
     fn set_curr_image(&mut self, idx: usize) {
         //  set prev scrollview
         let mut scrollview = self.prev.borrow_mut();
-        let config = Config::get_gallery().unwrap();
+        let config = self.config.clone();
+        let gallery = &config.gallery;
 
         {
             let mut img = scrollview.content.borrow_mut();
@@ -319,9 +511,11 @@ impl Preview {
             let i = idx as isize - 1;
             if i < 0 {
                 img.set_src("");
-            } else if let Some(pic) = config.pics.get(i as usize) {
+            } else if let Some(pic) = gallery.pics.get(i as usize) {
                 img.set_src(&pic.url);
-                img.load();
+                img.set_natural_size(pic.size());
+                img.set_fallback(&pic.preview);
+                img.load_with_priority(NEIGHBOUR_PRIORITY);
             } else {
                 img.set_src("");
             }
@@ -332,9 +526,11 @@ impl Preview {
         let mut scrollview = self.curr.borrow_mut();
         {
             let mut img = scrollview.content.borrow_mut();
-            if let Some(pic) = config.pics.get(idx) {
+            if let Some(pic) = gallery.pics.get(idx) {
                 img.set_src(&pic.url);
-                img.load();
+                img.set_natural_size(pic.size());
+                img.set_fallback(&pic.preview);
+                img.load_with_priority(CURRENT_PRIORITY);
             } else {
                 img.set_src("");
             }
@@ -345,22 +541,57 @@ impl Preview {
         let mut scrollview = self.next.borrow_mut();
         {
             let mut img = scrollview.content.borrow_mut();
-            if let Some(pic) = config.pics.get(idx + 1) {
+            if let Some(pic) = gallery.pics.get(idx + 1) {
                 img.set_src(&pic.url);
-                img.load();
+                img.set_natural_size(pic.size());
+                img.set_fallback(&pic.preview);
+                img.load_with_priority(NEIGHBOUR_PRIORITY);
             } else {
                 img.set_src("");
             }
         }
         scrollview.reset();
 
+        // keep images around the current one while paging
+        let srcs: Vec<&str> = (idx.max(1) - 1 .. idx + 2)
+            .filter_map(|i| gallery.pics.get(i))
+            .flat_map(|p| vec![p.url.as_str(), p.preview.as_str()])
+            .collect();
+        self.resources.pin_images(&srcs);
+
         self.img_idx = idx;
     }
 
+    /// slide to next image, -1 for previous one
+    fn slide(&mut self, mov: i32) {
+        let config = self.config.clone();
+        let gallery = &config.gallery;
+        // wait until current slide ends
+        if self.transition.is_some() || self.dragging {
+            return;
+        }
+        if mov == -1 && self.img_idx + 1 < gallery.pics.len() || mov == 1 && self.img_idx > 0 {
+            let target_x = mov * (self.width as i32 + PREVIEW_GAP);
+            self.move_to(target_x, self.config.transition_duration());
+        }
+    }
+
+    /// index of the image shown
+    pub fn current(&self) -> usize {
+        self.img_idx
+    }
+
+    fn jump_to(&mut self, idx: usize) {
+        self.transition = None;
+        self.translate_x = 0;
+        self.set_curr_image(idx);
+    }
+
     fn move_to(&mut self, x: i32, duration: Duration) {
         self.transition = Some(Transition::new(self.translate_x,
                                                x,
-                                               duration));
+                                               duration,
+                                               self.clock.clone()));
     }
 }
 
@@ -382,10 +613,14 @@ impl Display for Preview {
             self.next.borrow().render(canvas, r2);
         }
 
-        self.back_btn.render(canvas, rect);
+        // no chrome while zooming in or out
+        if rect.size() == (self.width, self.height) {
+            self.back_btn.render(canvas, rect);
+        }
     }
     fn handle_events(&mut self, evt: &Event) -> Option<Action> {
-        let config = Config::get_gallery().unwrap();
+        let config = self.config.clone();
+        let gallery = &config.gallery;
         self.gesture_detector.feed(evt);
 
         // single touch
@@ -394,14 +629,35 @@ impl Display for Preview {
                 let mut scrollview = self.curr.borrow_mut();
                 match event {
                     // double tap gesture
-                    &GestureEvent::DoubleTap(..) => {
+                    &GestureEvent::DoubleTap(x, y) => {
                         if scrollview.zoom_mode {
                             // exit zoom
                             scrollview.exit_zoom();
                         } else {
-                            scrollview.enter_zoom();
+                            scrollview.enter_zoom(x * self.width as f32,
+                                                  y * self.height as f32);
                         }
                     },
+                    // pinch gesture
+                    &GestureEvent::Pinch { center: (x, y), scale } => {
+                        scrollview.scale_by(x * self.width as f32,
+                                            y * self.height as f32,
+                                            scale);
+                    },
+                    &GestureEvent::PinchEnd => {
+                        // zoomed past limits, restore it
+                        scrollview.settle();
+                    },
+                    &GestureEvent::LongPress(..) => {
+                        scrollview.reset_zoom();
+                    },
+                    // mouse wheel zooms around mouse position
+                    &GestureEvent::Wheel { x, y, dy, .. } => {
+                        scrollview.scale_by(x * self.width as f32,
+                                            y * self.height as f32,
+                                            WHEEL_ZOOM.powf(dy));
+                        scrollview.settle();
+                    },
                     _ => ()
                 }
             }
@@ -409,14 +665,21 @@ impl Display for Preview {
             // handle horizontal move
             {
                 match event {
-                    &GestureEvent::PanStart { .. } => {
+                    &GestureEvent::PanStart { timestamp, .. } => {
                         self.dragging = true;
                         self.transition = None;
                         self.translate_x_pre = self.translate_x;
+                        self.swipe = None;
+                        self.pan_time = timestamp;
+                    },
+                    &GestureEvent::Swipe { direction, .. } => {
+                        self.swipe = Some(direction);
                     },
-                    &GestureEvent::Pan { x, y, mut dx, mut dy, .. } => {
+                    &GestureEvent::Pan { mut dx, mut dy, timestamp, .. } => {
                         dx *= self.width as f32;
                         dy *= self.height as f32;
+                        let frames = clock::frames_between(self.pan_time, timestamp);
+                        self.pan_time = timestamp;
 
                         let mut scrollview = self.curr.borrow_mut();
                         // if move is in opposite direction with outer tranlation
@@ -431,7 +694,7 @@ impl Display for Preview {
                         // then inner accept remaining move
                         if scrollview.zoom_mode {
                             // move inner
-                            let remain = scrollview.move_by(dx, dy);
+                            let remain = scrollview.move_by(dx, dy, frames);
                             dx = remain.0;
                         }
 
@@ -443,52 +706,55 @@ impl Display for Preview {
                         // move direction: -1 to left, 1 to right, 0 restore
                         let delta = self.translate_x - self.translate_x_pre;
                         let threshold = 50; // threshold for the move
-                        let mut mov = if delta > threshold {
+                        // a quick swipe turns the page even if it moved less than threshold
+                        let swipe = self.swipe.take();
+                        let mut mov = if delta > threshold || delta > 0 && swipe == Some(SwipeDirection::Right) {
                             1
-                        } else if delta < -threshold {
+                        } else if delta < -threshold || delta < 0 && swipe == Some(SwipeDirection::Left) {
                             -1
                         } else {
                             0
                         };
 
                         // duel with invalid move for the first slide and the last
-                        if mov == -1 && self.img_idx >= config.pics.len() - 1
+                        if mov == -1 && self.img_idx >= gallery.pics.len() - 1
                             || mov == 1 && self.img_idx == 0 {
                                 mov = 0;
                             }
                         let target_x = mov * (self.width as i32 + PREVIEW_GAP);
-                        self.move_to(target_x, Duration::from_millis(300));
+                        self.move_to(target_x, self.config.transition_duration());
                     },
                     _ => (),
                 }
             }
         }
 
-        let fingers = num_touch_fingers(1);
-        if fingers >= 2 {
-            // multi touch
-            let mut scrollview = self.curr.borrow_mut();
-            match evt {
-                // pinch gesture
-                &Event::MultiGesture {x, y, d_dist, ..} => {
-                    scrollview.scale_by(x * self.width as f32,
-                                        y * self.height as f32,
-                                        d_dist * 5.);
-                },
-                _ => ()
+        if let &Event::KeyDown { keycode: Some(key), .. } = evt {
+            let last = gallery.pics.len().max(1) - 1;
+            match key {
+                Keycode::Left | Keycode::PageUp => self.slide(1),
+                Keycode::Right | Keycode::PageDown => self.slide(-1),
+                Keycode::Home => self.jump_to(0),
+                Keycode::End => self.jump_to(last),
+                _ => (),
             }
         }
 
         return self.back_btn.handle_events(evt);
     }
     fn update(&mut self) {
-        // update scrollview slide animation
-        if !self.dragging {
-            let mut scrollview = self.curr.borrow_mut();
-            if scrollview.zoom_mode {
-                scrollview.update();
+        // a finger held still long enough
+        self.gesture_detector.update(clock::timestamp(self.clock.now()));
+        for event in self.gesture_detector.poll() {
+            if let GestureEvent::LongPress(..) = event {
+                self.curr.borrow_mut().reset_zoom();
             }
         }
+        let frames = self.frame_timer.tick();
+        // update scrollview zoom and slide animation
+        if !self.dragging {
+            self.curr.borrow_mut().update(frames);
+        }
 
         // check Preview horizontal slide end
         let mut in_transition = !self.dragging && self.transition.is_some();
@@ -499,7 +765,7 @@ impl Display for Preview {
                     in_transition = false;
                     self.translate_x = transition.target_val();
                 } else {
-                    self.translate_x = transition.step() as i32;
+                    self.translate_x = transition.step();
                 }
             }
             if !in_transition {
@@ -508,22 +774,45 @@ impl Display for Preview {
             }
         }
     }
-    fn on_start(&mut self, params: &Option<Box<Any>>) {
-        let mut i = 0;
-        if let &Some(ref p) = params {
-            if let Some(n) = p.downcast_ref::<usize>() {
-                i = *n;
+    fn layout(&mut self, rect: Rect) {
+        let rects = self.chrome.layout(rect);
+        self.width = rect.width();
+        self.height = rect.height();
+        if let Some(r) = rects.get("page") {
+            for v in [&self.prev, &self.curr, &self.next].iter() {
+                v.borrow_mut().set_rect(r.x(), r.y(), r.width(), r.height());
             }
         }
-        self.set_curr_image(i);
+        if let Some(r) = rects.get("back") {
+            self.back_btn.set_rect(r);
+        }
+        self.back_btn.set_screen_size(rect.size());
+        self.gesture_detector.set_screen_size(rect.size());
+        // pages are a screen apart
+        self.transition = None;
+        self.translate_x = 0;
+    }
+    fn on_stop(&mut self) {
+        // let the gallery evict the preview images
+        self.resources.pin_images(&[]);
     }
     fn is_interactive(&self) -> bool {
         true
     }
 }
 
+impl Scene for Preview {
+    /// index of the image shown first
+    type Params = usize;
+    fn on_start(&mut self, i: &usize) {
+        self.set_curr_image(*i);
+    }
+}
+
 pub struct ScrollView {
     pub content: Rc<RefCell<Image>>,
+    clock: Rc<Clock>,
+    config: Rc<Config>,
     rect: Rect,
     scale: f32,
     offset_x: f32,
@@ -536,12 +825,16 @@ pub struct ScrollView {
     // these mean are to track mean move speed
     mean_x: Mean<f32>,
     mean_y: Mean<f32>,
+    // animate (scale, offset_x, offset_y) together
+    transition: Option<Transition<(f32, f32, f32)>>,
 }
 
 impl ScrollView {
-    fn new(content: Rc<RefCell<Image>>) -> Rc<RefCell<ScrollView>> {
+    fn new(content: Rc<RefCell<Image>>, clock: Rc<Clock>, config: Rc<Config>) -> Rc<RefCell<ScrollView>> {
         Rc::new(RefCell::new(ScrollView {
             content,
+            clock,
+            config,
             rect: Rect::new(0, 0, 0, 0),
             scale: 1.0,
             offset_x: 0.,
@@ -553,33 +846,119 @@ impl ScrollView {
             dy: 0.,
             mean_x: Mean::new(3),
             mean_y: Mean::new(3),
+            transition: None,
         }))
     }
 
     pub fn reset(&mut self) {
+        self.transition = None;
         self.set_scale(1.);
         self.offset_x = 0.;
         self.offset_y = 0.;
     }
 
-    fn enter_zoom(&mut self) {
-        self.cover();
+    /// zoom to cover size toward point (x, y)
+    fn enter_zoom(&mut self, x: f32, y: f32) {
+        let scale = self.cover_scale();
+        let r = scale / self.scale;
+        let (px, py) = self.from_center(x, y);
+        let (x_limit, y_limit) = self.limits(scale);
+        let offset_x = limit(self.offset_x - px * (r - 1.), x_limit);
+        let offset_y = limit(self.offset_y - py * (r - 1.), y_limit);
+        self.animate_to(scale, offset_x, offset_y);
         self.zoom_mode = true;
     }
 
     fn exit_zoom(&mut self) {
-        self.contain();
-        self.offset_x = 0.;
-        self.offset_y = 0.;
+        self.animate_to(1., 0., 0.);
         self.zoom_mode = false;
     }
 
-    fn scale_by(&mut self, x: f32, y: f32, d: f32) {
-        let r = self.scale;
-        self.set_scale((r + d).max(0.1));
+    /// long press shows the whole image again
+    fn reset_zoom(&mut self) {
+        if self.zoom_mode {
+            self.exit_zoom();
+        }
     }
 
-    fn update(&mut self) {
+    /// animate back into zoom and offset limits
+    fn settle(&mut self) {
+        let scale = self.scale.clamp(1., MAX_SCALE);
+        let r = scale / self.scale;
+        let (x_limit, y_limit) = self.limits(scale);
+        let offset_x = limit(self.offset_x * r, x_limit);
+        let offset_y = limit(self.offset_y * r, y_limit);
+        if scale != self.scale || offset_x != self.offset_x || offset_y != self.offset_y {
+            self.animate_to(scale, offset_x, offset_y);
+        }
+        self.zoom_mode = scale > 1.;
+    }
+
+    fn animate_to(&mut self, scale: f32, offset_x: f32, offset_y: f32) {
+        self.transition = Some(Transition::new((self.scale, self.offset_x, self.offset_y),
+                                               (scale, offset_x, offset_y),
+                                               self.config.transition_duration(),
+                                               self.clock.clone()));
+        self.dx = 0.;
+        self.dy = 0.;
+    }
+
+    /// position of (x, y) relative to content center
+    fn from_center(&self, x: f32, y: f32) -> (f32, f32) {
+        let center = self.rect.center();
+        (x - center.x() as f32 - self.offset_x, y - center.y() as f32 - self.offset_y)
+    }
+
+    /// scale by ratio `r`, keep the content under point (x, y) in place,
+    /// zooming past limits is damped, call `settle` to restore
+    fn scale_by(&mut self, x: f32, y: f32, r: f32) {
+        self.transition = None;
+        let r = if self.scale >= MAX_SCALE && r > 1. || self.scale <= 1. && r < 1. {
+            r.powf(RUBBER_BAND_ZOOM)
+        } else {
+            r
+        };
+        let scale = (self.scale * r).clamp(MIN_SCALE, MAX_SCALE * 2.);
+        let r = scale / self.scale;
+        let (px, py) = self.from_center(x, y);
+        let offset_x = self.offset_x - px * (r - 1.);
+        let offset_y = self.offset_y - py * (r - 1.);
+
+        self.set_scale(scale);
+        // follow the fingers, offset is limited on settle
+        self.offset_x = offset_x;
+        self.offset_y = offset_y;
+        self.dx = 0.;
+        self.dy = 0.;
+    }
+
+    /// animate for `frames` 60fps frames
+    fn update(&mut self, frames: f32) {
+        let mut in_transition = self.transition.is_some();
+        if let Some(ref mut transition) = self.transition {
+            let (scale, offset_x, offset_y) = transition.step();
+            in_transition = !transition.at_end();
+            self.scale = scale;
+            self.offset_x = offset_x;
+            self.offset_y = offset_y;
+        }
+        if self.transition.is_some() {
+            let scale = self.scale;
+            let (x_limit, y_limit) = self.limits(scale);
+            self.offset_x_limit = x_limit;
+            self.offset_y_limit = y_limit;
+            if !in_transition {
+                self.transition = None;
+            }
+            return;
+        }
+
+        if self.offset_x.abs() > self.offset_x_limit || self.offset_y.abs() > self.offset_y_limit {
+            // released past border
+            self.settle();
+            return;
+        }
+
         if self.dx.abs() < 0.00001 && self.dy.abs() < 0.00001 {
             self.dx = 0.;
             self.dy = 0.;
@@ -587,11 +966,13 @@ impl ScrollView {
             return;
         }
 
-        self.dx = apply_friction(FRICTION, self.dx);
-        self.dy = apply_friction(FRICTION, self.dy);
+        let (mx, dx) = slide(self.config.friction, self.dx, frames);
+        let (my, dy) = slide(self.config.friction, self.dy, frames);
+        self.dx = dx;
+        self.dy = dy;
 
-        let offset_x = self.offset_x + self.dx;
-        let offset_y = self.offset_y + self.dy;
+        let offset_x = self.offset_x + mx;
+        let offset_y = self.offset_y + my;
         self.set_pos(offset_x, offset_y);
     }
 
@@ -602,26 +983,29 @@ impl ScrollView {
         self.rect.set_height(h);
     }
 
-    fn set_scale(&mut self, scale: f32) {
+    /// offset limits at scale
+    fn limits(&self, scale: f32) -> (f32, f32) {
         let w = self.rect.width();
         let h = self.rect.height();
 
         if let Some((img_w, img_h)) = self.content.borrow().get_img_size() {
             let (w2, h2) = Image::contain_size(img_w, img_h, w, h);
-            self.offset_x_limit = (scale * w2 as f32 - w as f32).max(0.) / 2.;
-            self.offset_y_limit = (scale * h2 as f32 - h as f32).max(0.) / 2.;
+            ((scale * w2 as f32 - w as f32).max(0.) / 2.,
+             (scale * h2 as f32 - h as f32).max(0.) / 2.)
+        } else {
+            (self.offset_x_limit, self.offset_y_limit)
         }
+    }
 
+    fn set_scale(&mut self, scale: f32) {
+        let (x_limit, y_limit) = self.limits(scale);
+        self.offset_x_limit = x_limit;
+        self.offset_y_limit = y_limit;
         self.scale = scale;
         self.zoom_mode = scale > 1.0;
     }
 
-    fn contain(&mut self) {
-        // scale 1.0 is contain size
-        self.set_scale(1.);
-    }
-
-    fn cover(&mut self) {
+    fn cover_scale(&self) -> f32 {
         let w = self.rect.width();
         let h = self.rect.height();
         let mut r = 2.;
@@ -630,45 +1014,25 @@ impl ScrollView {
             let (w2, _) = Image::contain_size(img_w, img_h, w, h);
             r = w1 as f32 / w2 as f32;
         }
-
-        self.set_scale(r);
-        self.dx = 0.;
-        self.dy = 0.;
+        r
     }
 
-    fn set_pos(&mut self, mut x: f32, mut y: f32) {
-        let mut x_limited = true;
-        let mut y_limited = true;
-        let offset_x_limit = self.offset_x_limit;
-        let offset_y_limit = self.offset_y_limit;
-
-        if x > offset_x_limit {
-            x = offset_x_limit;
-        } else if x < -offset_x_limit {
-            x = -offset_x_limit;
-        } else {
-            x_limited = false;
-        }
-        self.offset_x = x;
-
-        if y > offset_y_limit {
-            y = offset_y_limit;
-        } else if y < -offset_y_limit {
-            y = -offset_y_limit;
-        } else {
-            y_limited = false;
-        }
-        self.offset_y = y;
+    fn set_pos(&mut self, x: f32, y: f32) {
+        self.offset_x = limit(x, self.offset_x_limit);
+        self.offset_y = limit(y, self.offset_y_limit);
     }
 
-    pub fn move_by(&mut self, dx: f32, dy: f32) -> (f32, f32) {
+    /// move by (dx, dy) px taking `frames` 60fps frames, return move not consumed,
+    /// horizontal move stops at border, vertical move is damped past border
+    pub fn move_by(&mut self, dx: f32, dy: f32, frames: f32) -> (f32, f32) {
+        self.transition = None;
         let offset_x = self.offset_x + dx;
-        let offset_y = self.offset_y + dy;
-        self.set_pos(offset_x, offset_y);
+        self.offset_x = limit(offset_x, self.offset_x_limit);
+        self.offset_y += rubber_band(self.offset_y, dy, self.offset_y_limit);
 
         // get a mean to calc motion speed
-        self.mean_x.push(dx);
-        self.mean_y.push(dy);
+        self.mean_x.push(dx / frames);
+        self.mean_y.push(dy / frames);
 
         if self.offset_x_limit == self.offset_x.abs() {
             self.dx = 0.;
@@ -676,12 +1040,12 @@ impl ScrollView {
             self.dx = self.mean_x.get() as f32;
         }
 
-        if self.offset_y_limit == self.offset_y.abs() {
+        if self.offset_y_limit <= self.offset_y.abs() {
             self.dy = 0.;
         } else {
             self.dy = self.mean_y.get() as f32;
         }
-        (offset_x - self.offset_x, offset_y - self.offset_y)
+        (offset_x - self.offset_x, 0.)
     }
 }
 
@@ -696,6 +1060,32 @@ impl Display for ScrollView {
     }
 }
 
+/// limit x in [-l, l]
+fn limit(x: f32, l: f32) -> f32 {
+    x.max(-l).min(l)
+}
+
+/// damped move of `d` at `pos`, when it moves past [-l, l]
+fn rubber_band(pos: f32, d: f32, l: f32) -> f32 {
+    let over = if d > 0. { pos + d - l } else { -l - pos - d };
+    if over <= 0. {
+        return d;
+    }
+    let k = over / 100.;
+    d * if k < PI / 2. { k.cos() } else { 0. }
+}
+
+/// slide with speed `v` for `frames` 60fps frames, return moved distance and new speed,
+/// the distance does not depend on how the time is split into frames
+fn slide(friction: f32, v: f32, frames: f32) -> (f32, f32) {
+    let v1 = apply_friction(friction * frames, v);
+    if v1 == 0. {
+        // stopped during this step
+        return (v * v.abs() / (2. * friction), 0.);
+    }
+    ((v + v1) / 2. * frames, v1)
+}
+
 fn apply_friction(friction: f32, dx: f32) -> f32 {
     let dx = if dx.abs() < friction {
         0.
@@ -708,3 +1098,113 @@ fn apply_friction(friction: f32, dx: f32) -> f32 {
     }
     dx
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// distance of a slide started at speed v, updated at fps
+    fn slide_dist(v: f32, fps: f32) -> f32 {
+        let (mut v, mut total) = (v, 0.);
+        while v != 0. {
+            let (d, v1) = slide(2., v, 60. / fps);
+            total += d;
+            v = v1;
+        }
+        total
+    }
+
+    #[test]
+    fn slide_independent_of_frame_rate() {
+        let d30 = slide_dist(40., 30.);
+        let d144 = slide_dist(40., 144.);
+        assert!((d30 - 400.).abs() < 0.01);
+        assert!((d30 - d144).abs() < 0.01);
+        assert!((slide_dist(-40., 60.) + 400.).abs() < 0.01);
+    }
+
+    #[test]
+    fn rubber_band_damps_past_border() {
+        // inside border moves freely
+        assert_eq!(rubber_band(0., 10., 50.), 10.);
+        assert_eq!(rubber_band(0., -10., 50.), -10.);
+        // past border moves less, and less further out
+        let d1 = rubber_band(60., 10., 50.);
+        let d2 = rubber_band(100., 10., 50.);
+        assert!(d1 < 10. && d2 < d1 && d2 > 0.);
+        assert_eq!(rubber_band(-300., -10., 50.), 0.);
+        // moving back is not damped
+        assert_eq!(rubber_band(100., -10., 50.), -10.);
+    }
+
+    #[test]
+    fn replay_scroll_and_swipe() {
+        use sdl2::mouse::MouseWheelDirection;
+        use sdl2::pixels::PixelFormatEnum;
+        use sdl2::surface::Surface;
+        use model::GalleryItem;
+        use clock::ManualClock;
+        use replay::{Recorder, Replay};
+
+        let pics = (0..30).map(|i| GalleryItem {
+            url: format!("missing/{}.jpg", i),
+            preview: format!("missing/thumbs/{}.jpg", i),
+            ..Default::default()
+        }).collect();
+        let config = Rc::new(Config { width: 320, height: 480, gallery: Gallery { pics }, ..Default::default() });
+
+        // an offscreen canvas needs no video driver, the views never render
+        let canvas = Surface::new(320, 480, PixelFormatEnum::RGBA8888).unwrap().into_canvas().unwrap();
+        let clock = Rc::new(ManualClock::new());
+        let stage = Stage::with_clock(canvas.texture_creator(), config, clock.clone());
+        let gallery = GalleryView::new(stage.clone());
+        let preview = Preview::new(stage.clone());
+        {
+            let mut s = stage.borrow_mut();
+            s.add_scene(GALLERY, gallery.clone());
+            s.add_scene(PREVIEW, preview.clone());
+            s.navigator().push(GALLERY, ());
+        }
+
+        /// record `events` 50ms apart from `start`, then a second of frames
+        fn record(start: Duration, events: Vec<Event>) -> Replay {
+            let clock = Rc::new(ManualClock::new());
+            clock.set(start);
+            let mut out = vec![];
+            {
+                let mut recorder = Recorder::new(clock.clone(), &mut out);
+                for e in events {
+                    recorder.record_event(&e);
+                    clock.advance(Duration::from_millis(50));
+                    recorder.record_frame();
+                }
+                for _ in 0..60 {
+                    clock.advance(Duration::from_millis(16));
+                    recorder.record_frame();
+                }
+            }
+            Replay::load(&out[..]).unwrap()
+        }
+        // two wheel ticks scroll two steps down
+        let scroll = record(clock.now(), vec![
+            Event::MouseWheel { timestamp: 0, window_id: 0, which: 0, x: 0, y: -1, direction: MouseWheelDirection::Normal },
+            Event::MouseWheel { timestamp: 50, window_id: 0, which: 0, x: 0, y: -1, direction: MouseWheelDirection::Normal },
+        ]);
+        scroll.run(&mut *stage.borrow_mut(), &clock);
+        assert_eq!(gallery.borrow().translate_y, -2. * WHEEL_STEP);
+
+        stage.borrow_mut().navigator().push(PREVIEW, 4);
+        // drag half the screen to the left turns to the next image
+        let mut events = vec![
+            Event::FingerDown { timestamp: 0, touch_id: 0, finger_id: 0, x: 0.8, y: 0.5, dx: 0., dy: 0., pressure: 1. },
+        ];
+        events.extend((1..6).map(|i| Event::FingerMotion {
+            timestamp: i * 50, touch_id: 0, finger_id: 0, x: 0.8 - 0.1 * i as f32, y: 0.5, dx: -0.1, dy: 0., pressure: 1.,
+        }));
+        events.push(Event::FingerUp { timestamp: 300, touch_id: 0, finger_id: 0, x: 0.3, y: 0.5, dx: 0., dy: 0., pressure: 0. });
+        let swipe = record(clock.now(), events);
+        swipe.run(&mut *stage.borrow_mut(), &clock);
+        let p = preview.borrow();
+        assert_eq!((p.img_idx, p.translate_x), (5, 0));
+    }
+}
@@ -1,4 +1,3 @@
-use std::process;
 use sdl2::{ self, Sdl };
 use sdl2::pixels::Color;
 use sdl2::event::Event;
@@ -12,6 +11,7 @@ use utils;
 use mandelbrot::{Mandelbrot};
 use sdl2::ttf::Sdl2TtfContext;
 use utils::glyph_renderer::GlyphRenderer;
+use main_loop::{self, LoopControl};
 
 pub struct App<'a> {
     canvas: Canvas<Window>,
@@ -24,7 +24,7 @@ pub struct App<'a> {
 
 impl<'a> App<'a> {
     pub fn new(ctx: &Sdl, ttf_context: &'a Sdl2TtfContext) -> Self {
-        let (width, height) = utils::get_window_dimention();
+        let (width, height) = main_loop::get_canvas_dimention();
 
         let video = ctx.video().unwrap();
 
@@ -61,29 +61,16 @@ impl<'a> App<'a> {
         }
     }
 
-    pub fn start(&mut self) {
-        #[cfg(target_os = "emscripten")]
-        {
-            use emscripten;
-            unsafe {
-                emscripten::emscripten_cancel_main_loop();
-            }
-            emscripten::set_main_loop_callback(|| self.mainloop());
-        }
-
-        #[cfg(not(target_os = "emscripten"))]
-        {
-            loop {
-                self.mainloop();
-            }
-        }
+    /// replaces the running app, if started again
+    pub fn start(mut self) where 'a: 'static {
+        main_loop::run_main_loop(move || self.mainloop());
     }
 
-    fn mainloop(&mut self) {
+    fn mainloop(&mut self) -> LoopControl {
         for event in self.events.poll_iter() {
             match event {
                 Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
-                    process::exit(1);
+                    return LoopControl::Exit;
                 },
                 Event::KeyDown {keycode: Some(Keycode::Space), ..} => {
                     self.mandelbrot.reset();
@@ -129,5 +116,6 @@ impl<'a> App<'a> {
             self.glyph_renderer.render(&mut self.canvas, &label, 10, 10);
         }
         let _ = self.canvas.present();
+        LoopControl::Continue
     }
 }
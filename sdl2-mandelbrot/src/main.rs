@@ -3,9 +3,8 @@ extern crate sdl2;
 #[macro_use]
 extern crate stdweb;
 extern crate num;
+extern crate main_loop;
 
-#[cfg(target_os = "emscripten")]
-mod emscripten;
 mod utils;
 mod app;
 mod mandelbrot;
@@ -35,9 +34,10 @@ fn main() {
 
 fn start() {
     let ctx = sdl2::init().unwrap();
-    let ttf_context = ttf::init().unwrap();
+    // fonts borrow the ttf context, the main loop keeps it for good
+    let ttf_context: &'static ttf::Sdl2TtfContext = Box::leak(Box::new(ttf::init().unwrap()));
 
-    let mut app = App::new(&ctx, &ttf_context);
+    let app = App::new(&ctx, ttf_context);
     app.start();
 }
 
@@ -7,7 +7,7 @@ use sdl2::render::{ TextureCreator, Texture, RenderTarget };
 use sdl2::surface::Surface;
 use sdl2::rect::Rect;
 use sdl2::image::LoadTexture;
-use utils;
+use main_loop;
 
 const R1: Complex<f64> = Complex {re: -2.0, im: -1.0};
 const R2: Complex<f64> = Complex {re: 1.0, im: 1.0};
@@ -42,7 +42,7 @@ impl Mandelbrot {
     }
 
     pub fn update(&mut self) {
-        let (width, height) = utils::get_window_dimention();
+        let (width, height) = main_loop::get_canvas_dimention();
 
         let t0 = Instant::now();
         let mut surface = Surface::new(width, height, PixelFormatEnum::RGB24).unwrap();
@@ -73,7 +73,7 @@ impl Mandelbrot {
     pub fn update_rect(&mut self, rect: &Rect) {
         let p1 = rect.top_left();
         let p2 = rect.bottom_right();
-        let (width, height) = utils::get_window_dimention();
+        let (width, height) = main_loop::get_canvas_dimention();
         let width = width as usize;
         let height = height as usize;
         let r1 = pixel_to_point(p1.x() as usize, p1.y() as usize,
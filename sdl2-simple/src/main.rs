@@ -1,6 +1,6 @@
 extern crate sdl2;
+extern crate main_loop;
 
-use std::process;
 use std::path::Path;
 use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
@@ -9,9 +9,7 @@ use sdl2::rect::{Point, Rect};
 use sdl2::keyboard::Keycode;
 use sdl2::render::TextureCreator;
 use sdl2::gfx::primitives::DrawRenderer;
-
-#[cfg(target_os = "emscripten")]
-pub mod emscripten;
+use main_loop::LoopControl;
 
 fn main() {
     // sdl2_image::init();
@@ -37,7 +35,8 @@ fn main() {
         .build()
         .unwrap();
 
-    let tc = canvas.texture_creator();
+    // textures borrow their creator, the main loop keeps it for good
+    let tc: &'static TextureCreator<_> = Box::leak(Box::new(canvas.texture_creator()));
     let texture = tc.load_texture(Path::new("assets/icon.png")).expect("Cannot load image");
 
     let black = Color::RGB(0, 0, 0);
@@ -50,11 +49,11 @@ fn main() {
 
     let mut events = ctx.event_pump().unwrap();
 
-    let mut main_loop = || {
+    main_loop::run_main_loop(move || {
         for event in events.poll_iter() {
             match event {
                 Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
-                    process::exit(1);
+                    return LoopControl::Exit;
                 },
                 _ => {}
             }
@@ -76,14 +75,6 @@ fn main() {
         let _ = canvas.aa_line(150, 10, 250, 110, red);
         let _ = canvas.copy(&texture, None, Rect::new(70, 10, 50, 50));
         canvas.present();
-    };
-
-    #[cfg(target_os = "emscripten")]
-    use emscripten::{emscripten};
-
-    #[cfg(target_os = "emscripten")]
-    emscripten::set_main_loop_callback(main_loop);
-
-    #[cfg(not(target_os = "emscripten"))]
-    loop { main_loop(); }
+        LoopControl::Continue
+    });
 }
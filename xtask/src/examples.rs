@@ -0,0 +1,109 @@
+// Examples the build driver knows about, and where their web build goes.
+
+pub struct Example {
+    /// directory under the workspace root
+    pub dir: &'static str,
+    pub package: &'static str,
+    /// binary to build when the package has several
+    pub bin: Option<&'static str>,
+    /// web build output, relative to `dir`
+    pub output: &'static str,
+    /// name for the emscripten js loader, `None` keeps the built name
+    pub js_name: Option<&'static str>,
+    /// the link args write the output already, nothing to copy
+    pub linked_output: bool,
+    /// builds for pc too
+    pub native: bool,
+    /// default to a release build
+    pub release: bool,
+    /// shrink the release wasm with `wasm-gc`
+    pub wasm_gc: bool,
+    /// served by `npm run serve` from `output`'s parent instead of the built-in server
+    pub npm: bool,
+}
+
+const DEFAULT: Example = Example {
+    dir: "",
+    package: "",
+    bin: None,
+    output: "static",
+    js_name: Some("app.js"),
+    linked_output: false,
+    native: false,
+    release: false,
+    wasm_gc: false,
+    npm: false,
+};
+
+pub const EXAMPLES: &[Example] = &[
+    Example { dir: "dom", package: "dom", ..DEFAULT },
+    Example { dir: "emscripten-api", package: "emscripten-api", release: true, ..DEFAULT },
+    Example { dir: "ffi", package: "ffi", release: true, ..DEFAULT },
+    Example { dir: "file-read", package: "file-read", linked_output: true, ..DEFAULT },
+    Example { dir: "hello-world", package: "hello-world", release: true, ..DEFAULT },
+    Example { dir: "sdl2-drag", package: "sdl2-drag", native: true, ..DEFAULT },
+    Example {
+        dir: "sdl2-gallery", package: "sdl2-gallery", bin: Some("sdl2-gallery"),
+        native: true, wasm_gc: true, ..DEFAULT
+    },
+    Example {
+        dir: "sdl2-mandelbrot", package: "sdl2-mandelbrot", output: "static/public",
+        js_name: None, native: true, wasm_gc: true, npm: true, ..DEFAULT
+    },
+    Example { dir: "sdl2-simple", package: "sdl2-simple", native: true, ..DEFAULT },
+];
+
+pub fn find(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|e| e.dir == name || e.package == name)
+}
+
+impl Example {
+    /// rust crate name of the built binary
+    pub fn crate_name(&self) -> String {
+        self.bin.unwrap_or(self.package).replace('-', "_")
+    }
+
+    /// built files of this example, among the files of all examples in `deps`
+    pub fn is_output(&self, file_name: &str) -> bool {
+        let name = self.crate_name();
+        if !file_name.starts_with(&name) {
+            return false;
+        }
+        let rest = &file_name[name.len()..];
+        (rest.starts_with('-') || rest.starts_with('.')) && output_ext(file_name).is_some()
+    }
+}
+
+/// emscripten outputs copied to the web dir
+pub fn output_ext(file_name: &str) -> Option<&'static str> {
+    if file_name.ends_with(".asm.js") {
+        None
+    } else if file_name.ends_with(".js") {
+        Some("js")
+    } else if file_name.ends_with(".wasm") {
+        Some("wasm")
+    } else if file_name.ends_with(".data") {
+        Some("data")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_of_one_example() {
+        let e = find("sdl2-gallery").unwrap();
+        assert!(e.is_output("sdl2_gallery-1f2e3d.js"));
+        assert!(e.is_output("sdl2_gallery.wasm"));
+        assert!(e.is_output("sdl2_gallery.data"));
+        assert!(!e.is_output("sdl2_gallery-1f2e3d.asm.js"));
+        assert!(!e.is_output("sdl2_gallery-1f2e3d.d"));
+        // gallery-manifest and other examples share the deps dir
+        assert!(!e.is_output("gallery_manifest-1f2e3d.js"));
+        assert!(!e.is_output("sdl2_gallery_extra.wasm"));
+        assert!(find("sdl2").is_none());
+    }
+}
@@ -0,0 +1,222 @@
+// Build driver for the examples, run with `cargo xtask`.
+//
+//     cargo xtask list
+//     cargo xtask build <example> [--web] [--release | --debug]
+//     cargo xtask run <example> [--release] [--bin <name>] [-- <args>]
+//     cargo xtask serve <example> [--port <port>]
+//
+// Cargo runs from the example directory so its `.cargo/config` applies,
+// rustc itself runs from the workspace root, so paths in the link args
+// there are relative to the root.
+mod examples;
+mod serve;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::SystemTime;
+use examples::{Example, EXAMPLES};
+
+const WEB_TARGET: &str = "wasm32-unknown-emscripten";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        println!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let cmd = args.first().map(|s| s.as_str()).unwrap_or("help");
+    if cmd == "list" {
+        for e in EXAMPLES {
+            println!("{:16} {}", e.dir, if e.native { "web, native" } else { "web" });
+        }
+        return Ok(());
+    }
+    if cmd == "help" || cmd == "--help" || cmd == "-h" {
+        usage();
+        return Ok(());
+    }
+
+    let name = args.get(1).ok_or("no example given, see `cargo xtask list`")?;
+    let example = examples::find(name).ok_or(format!("unknown example `{}`, see `cargo xtask list`", name))?;
+    let opts = Options::parse(example, &args[2..])?;
+    match cmd {
+        "build" if opts.web => build_web(example, &opts),
+        "build" => build_native(example, &opts),
+        "run" => run_native(example, &opts),
+        "serve" => serve(example, &opts),
+        _ => {
+            usage();
+            Err(format!("unknown command `{}`", cmd))
+        },
+    }
+}
+
+fn usage() {
+    println!("usage:
+    cargo xtask list
+    cargo xtask build <example> [--web] [--release | --debug]
+    cargo xtask run <example> [--release] [--bin <name>] [-- <args>]
+    cargo xtask serve <example> [--port <port>]");
+}
+
+struct Options {
+    web: bool,
+    release: bool,
+    bin: Option<String>,
+    port: u16,
+    /// passed to the example after `--`
+    rest: Vec<String>,
+}
+
+impl Options {
+    fn parse(example: &Example, args: &[String]) -> Result<Options, String> {
+        let mut opts = Options {
+            web: false,
+            release: example.release,
+            bin: example.bin.map(|b| b.to_owned()),
+            port: 8000,
+            rest: vec![],
+        };
+        let mut args = args.iter();
+        while let Some(a) = args.next() {
+            match a.as_str() {
+                "--web" => opts.web = true,
+                "--release" => opts.release = true,
+                "--debug" => opts.release = false,
+                "--bin" => opts.bin = Some(args.next().ok_or("--bin needs a name")?.to_owned()),
+                "--port" => {
+                    let port = args.next().ok_or("--port needs a number")?;
+                    opts.port = port.parse().map_err(|_| format!("bad port `{}`", port))?;
+                },
+                "--" => {
+                    opts.rest = args.cloned().collect();
+                    break;
+                },
+                _ => return Err(format!("unknown option `{}`", a)),
+            }
+        }
+        Ok(opts)
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    // xtask is one level below the root
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_owned()
+}
+
+fn example_dir(example: &Example) -> PathBuf {
+    workspace_root().join(example.dir)
+}
+
+/// cargo invoked from the example directory
+fn cargo(example: &Example, cmd: &str, opts: &Options) -> Command {
+    let mut c = Command::new(env::var("CARGO").unwrap_or("cargo".to_owned()));
+    c.current_dir(example_dir(example))
+        .arg(cmd)
+        .arg("--package").arg(example.package);
+    if let Some(ref bin) = opts.bin {
+        c.arg("--bin").arg(bin);
+    }
+    if opts.release {
+        c.arg("--release");
+    }
+    c
+}
+
+fn exec(mut c: Command) -> Result<(), String> {
+    let status = c.status().map_err(|e| format!("cannot run {:?}: {}", c, e))?;
+    if !status.success() {
+        return Err(format!("{:?} failed: {}", c, status));
+    }
+    Ok(())
+}
+
+fn build_native(example: &Example, opts: &Options) -> Result<(), String> {
+    if !example.native {
+        return Err(format!("{} only builds for the web, use --web", example.dir));
+    }
+    exec(cargo(example, "build", opts))
+}
+
+fn run_native(example: &Example, opts: &Options) -> Result<(), String> {
+    if !example.native {
+        return Err(format!("{} only runs on the web, see `cargo xtask serve`", example.dir));
+    }
+    let mut c = cargo(example, "run", opts);
+    if !opts.rest.is_empty() {
+        c.arg("--").args(&opts.rest);
+    }
+    exec(c)
+}
+
+/// build for emscripten and copy js, wasm and data to the example's web dir
+fn build_web(example: &Example, opts: &Options) -> Result<(), String> {
+    let mut c = cargo(example, "build", opts);
+    c.arg("--target").arg(WEB_TARGET);
+    exec(c)?;
+    if example.linked_output {
+        return Ok(());
+    }
+
+    let profile = if opts.release { "release" } else { "debug" };
+    let deps = workspace_root().join("target").join(WEB_TARGET).join(profile).join("deps");
+    let output = example_dir(example).join(example.output);
+    fs::create_dir_all(&output).map_err(|e| format!("{}: {}", output.display(), e))?;
+
+    for (ext, file) in latest_outputs(example, &deps)? {
+        let file_name = file.file_name().unwrap().to_string_lossy().into_owned();
+        let dest = match (ext, example.js_name) {
+            ("js", Some(js_name)) => output.join(js_name),
+            _ => output.join(&file_name),
+        };
+        println!("copy {} -> {}", file.display(), dest.display());
+        fs::copy(&file, &dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+        if ext == "wasm" && opts.release && example.wasm_gc {
+            // optional, only shrinks the file
+            if let Err(e) = exec({ let mut c = Command::new("wasm-gc"); c.arg(&dest); c }) {
+                println!("skip wasm-gc: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// newest js, wasm and data file of `example` in `deps`
+fn latest_outputs(example: &Example, deps: &Path) -> Result<Vec<(&'static str, PathBuf)>, String> {
+    let mut latest: Vec<(&'static str, SystemTime, PathBuf)> = vec![];
+    let entries = fs::read_dir(deps).map_err(|e| format!("{}: {}", deps.display(), e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !example.is_output(&name) {
+            continue;
+        }
+        let ext = examples::output_ext(&name).unwrap();
+        let modified = entry.metadata().and_then(|m| m.modified()).map_err(|e| e.to_string())?;
+        match latest.iter().position(|&(e, _, _)| e == ext) {
+            Some(i) => if modified > latest[i].1 {
+                latest[i] = (ext, modified, entry.path());
+            },
+            None => latest.push((ext, modified, entry.path())),
+        }
+    }
+    if !latest.iter().any(|&(e, _, _)| e == "js") {
+        return Err(format!("no js output of {} in {}", example.dir, deps.display()));
+    }
+    Ok(latest.into_iter().map(|(ext, _, path)| (ext, path)).collect())
+}
+
+fn serve(example: &Example, opts: &Options) -> Result<(), String> {
+    let output = example_dir(example).join(example.output);
+    if example.npm {
+        // a vue app that bundles the web build
+        let mut c = Command::new("npm");
+        c.current_dir(output.parent().unwrap()).arg("run").arg("serve");
+        return exec(c);
+    }
+    serve::serve(&output, opts.port).map_err(|e| e.to_string())
+}
@@ -0,0 +1,146 @@
+// Static file server for the web builds.
+// Only GET and HEAD of files under the root, enough for a local browser.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+
+pub fn serve(root: &Path, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("serving {} at http://localhost:{}", root.display(), port);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                println!("connection failed: {}", e);
+                continue;
+            },
+        };
+        let root = root.to_owned();
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &root) {
+                println!("request failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, root: &Path) -> io::Result<()> {
+    let mut request_line = String::new();
+    {
+        let mut reader = BufReader::new(&stream);
+        reader.read_line(&mut request_line)?;
+        // skip headers
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let url = parts.next().unwrap_or("/");
+    if method != "GET" && method != "HEAD" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"method not allowed");
+    }
+
+    let path = match resolve(root, url) {
+        Some(p) => p,
+        None => return respond(&mut stream, "403 Forbidden", "text/plain", b"forbidden"),
+    };
+    let mut body = vec![];
+    match File::open(&path).and_then(|mut f| f.read_to_end(&mut body)) {
+        Ok(_) => {
+            println!("{} {}", method, url);
+            write_head(&mut stream, "200 OK", mime_type(&path), body.len())?;
+            if method == "GET" {
+                stream.write_all(&body)?;
+            }
+            stream.flush()
+        },
+        Err(_) => {
+            println!("{} {} not found", method, url);
+            respond(&mut stream, "404 Not Found", "text/plain", b"not found")
+        },
+    }
+}
+
+fn write_head(stream: &mut TcpStream, status: &str, mime: &str, len: usize) -> io::Result<()> {
+    write!(stream, "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\n\r\n",
+           status, mime, len)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, mime: &str, body: &[u8]) -> io::Result<()> {
+    write_head(stream, status, mime, body.len())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// file for `url` under `root`, none if it points outside
+pub fn resolve(root: &Path, url: &str) -> Option<PathBuf> {
+    let path = url.split(&['?', '#'][..]).next().unwrap_or("");
+    let path = decode(path)?;
+    let mut file = root.to_owned();
+    for c in Path::new(path.trim_start_matches('/')).components() {
+        match c {
+            Component::Normal(p) => file.push(p),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+    if file.is_dir() {
+        file.push("index.html");
+    }
+    Some(file)
+}
+
+/// undo %xx escapes
+fn decode(s: &str) -> Option<String> {
+    let mut out = vec![];
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+pub fn mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "html" => "text/html; charset=utf-8",
+        "js" => "application/javascript",
+        "css" => "text/css",
+        "json" => "application/json",
+        "toml" | "txt" => "text/plain; charset=utf-8",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_stays_in_root() {
+        let root = Path::new("/srv/static");
+        assert_eq!(resolve(root, "/img/a%20b.jpg?v=1"), Some(PathBuf::from("/srv/static/img/a b.jpg")));
+        assert_eq!(resolve(root, "/./app.js#x"), Some(PathBuf::from("/srv/static/app.js")));
+        assert_eq!(resolve(root, "/../secret"), None);
+        assert_eq!(resolve(root, "/img/%2e%2e/%2e%2e/secret"), None);
+        assert_eq!(mime_type(Path::new("sdl2_gallery.wasm")), "application/wasm");
+    }
+}